@@ -0,0 +1,87 @@
+//-----------------------------------------------------
+// Link graph
+//-----------------------------------------------------
+
+// LINK_GRAPH を簡易的な力学モデルで配置し、 SVG に描画する
+function renderLinkGraph() {
+  const svg = document.getElementById("link-graph");
+  const width = svg.clientWidth;
+  const height = svg.clientHeight;
+  const pathToRoot =
+    document.head.querySelector('meta[name="path_to_root"]')?.content ?? ".";
+
+  const nodes = LINK_GRAPH.nodes.map((n) => ({
+    ...n,
+    x: Math.random() * width,
+    y: Math.random() * height,
+    vx: 0,
+    vy: 0,
+  }));
+  const index = new Map(nodes.map((n, i) => [n.path, i]));
+  const links = LINK_GRAPH.links.map((l) => [
+    index.get(l.source),
+    index.get(l.target),
+  ]);
+
+  for (let step = 0; step < 300; step++) {
+    // ノード同士は反発する
+    for (let i = 0; i < nodes.length; i++) {
+      for (let j = i + 1; j < nodes.length; j++) {
+        const dx = nodes[j].x - nodes[i].x;
+        const dy = nodes[j].y - nodes[i].y;
+        const d2 = Math.max(dx * dx + dy * dy, 1);
+        const f = 2000 / d2;
+        nodes[i].vx -= dx * f * 0.01;
+        nodes[i].vy -= dy * f * 0.01;
+        nodes[j].vx += dx * f * 0.01;
+        nodes[j].vy += dy * f * 0.01;
+      }
+    }
+    // リンクで結ばれたノードは引き合う
+    for (const [s, t] of links) {
+      const dx = nodes[t].x - nodes[s].x;
+      const dy = nodes[t].y - nodes[s].y;
+      nodes[s].vx += dx * 0.01;
+      nodes[s].vy += dy * 0.01;
+      nodes[t].vx -= dx * 0.01;
+      nodes[t].vy -= dy * 0.01;
+    }
+    // 中心に寄せつつ、減衰させる
+    for (const n of nodes) {
+      n.vx += (width / 2 - n.x) * 0.002;
+      n.vy += (height / 2 - n.y) * 0.002;
+      n.x = Math.min(Math.max(n.x + n.vx, 10), width - 10);
+      n.y = Math.min(Math.max(n.y + n.vy, 10), height - 10);
+      n.vx *= 0.6;
+      n.vy *= 0.6;
+    }
+  }
+
+  const ns = "http://www.w3.org/2000/svg";
+  for (const [s, t] of links) {
+    const line = document.createElementNS(ns, "line");
+    line.setAttribute("x1", nodes[s].x);
+    line.setAttribute("y1", nodes[s].y);
+    line.setAttribute("x2", nodes[t].x);
+    line.setAttribute("y2", nodes[t].y);
+    line.setAttribute("class", "graph-link");
+    svg.appendChild(line);
+  }
+  for (const n of nodes) {
+    const a = document.createElementNS(ns, "a");
+    a.setAttribute("href", `${pathToRoot}/${pageHref(n.path)}`);
+    const circle = document.createElementNS(ns, "circle");
+    circle.setAttribute("cx", n.x);
+    circle.setAttribute("cy", n.y);
+    circle.setAttribute("r", 5);
+    const label = document.createElementNS(ns, "text");
+    label.setAttribute("x", n.x + 8);
+    label.setAttribute("y", n.y + 4);
+    label.textContent = n.title;
+    a.append(circle, label);
+    a.setAttribute("class", "graph-node");
+    svg.appendChild(a);
+  }
+}
+
+window.addEventListener("DOMContentLoaded", renderLinkGraph);
@@ -1,7 +1,35 @@
+function pathToRoot() {
+  return (
+    document.head.querySelector('meta[name="path_to_root"]')?.content ?? "."
+  );
+}
+
+// 記事の出力先のパスを、リンクに書く URL に変換する
+// pretty URL の場合は、末尾の index.html を省く
+function pageHref(path) {
+  if (!PRETTY_URLS) return path;
+  return path.replace(/(^|\/)index\.html$/, "$1") || "./";
+}
+
+// ページの言語の記事一覧や検索の索引を置くディレクトリ (既定の言語の場合は空)
+function langDir() {
+  const lang = document.documentElement.lang;
+  return lang === SITE_LANGUAGE ? "" : `${lang}/`;
+}
+
+// ページと同じ言語の記事 (検索の索引の文書番号の順)
+let langPagesCache;
+function langPages() {
+  langPagesCache ??= METADATA.filter(
+    (page) => page.lang === document.documentElement.lang,
+  );
+  return langPagesCache;
+}
+
 function createTagElem(tagName) {
   const a = document.createElement("a");
   a.className = "tag";
-  a.href = `index.html?tag=${tagName}`;
+  a.href = `${pathToRoot()}/${pageHref(`${langDir()}index.html`)}?tag=${tagName}`;
   a.innerHTML = tagName;
   return a;
 }
@@ -15,10 +43,13 @@ function createCard(page) {
 
   const content = template.content.cloneNode(true);
   const card = content.querySelector(".card");
-  card.href = page.path;
-  if (page.path.startsWith("private/")) card.classList.add("crypto");
+  card.href = `${pathToRoot()}/${pageHref(page.path)}`;
+  if (page.crypto) card.classList.add("crypto");
 
   content.querySelector(".card-header").innerHTML = page.title;
+  const description = content.querySelector(".card-description");
+  if (page.description) description.textContent = page.description;
+  else description.remove();
   content.querySelector(".card-date").innerHTML = page.update;
 
   const tags = content.querySelector(".card-tags");
@@ -28,48 +59,24 @@ function createCard(page) {
   return content;
 }
 
-function renderPageList() {
-  const fragment = document.createDocumentFragment();
-
-  METADATA.forEach((page) => fragment.appendChild(createCard(page)));
-
-  document.getElementById("contents-list").appendChild(fragment);
-}
-
-function renderTagSet() {
-  const tagSet = new Set(
-    Object.keys(METADATA)
-      .map((key) => METADATA[key].tags)
-      .flat(),
-  );
-
-  const fragment = document.createDocumentFragment();
-  tagSet.forEach((tagName) => {
-    fragment.appendChild(createTagElem(tagName));
-    fragment.appendChild(document.createTextNode(" "));
-  });
-
-  document.getElementById("tags-list").appendChild(fragment);
-}
-
 function indexMain() {
+  // 記事一覧はビルド時に描画済みなので、タグでの絞り込み時のみ書き換える
   const params = new URLSearchParams(window.location.search);
-  if (!params.has("tag")) {
-    renderPageList();
-    renderTagSet();
-  } else {
-    const tag = params.get("tag");
-    const tagElem = createTagElem(tag);
+  if (!params.has("tag")) return;
 
-    document.getElementById("tag-filter").appendChild(tagElem);
+  const tag = params.get("tag");
+  const tagElem = createTagElem(tag);
 
-    const fragment = document.createDocumentFragment();
-    METADATA.filter((page) => page.tags.includes(tag)).forEach((page) =>
-      fragment.appendChild(createCard(page)),
-    );
+  document.getElementById("tag-filter").appendChild(tagElem);
+  document.querySelector(".pagination")?.remove();
 
-    document.getElementById("contents-list").appendChild(fragment);
-  }
+  const fragment = document.createDocumentFragment();
+  const pages = langPages().filter(
+    (page) => !page.unlisted && page.tags.includes(tag),
+  );
+  pages.forEach((page) => fragment.appendChild(createCard(page)));
+
+  document.getElementById("contents-list").replaceChildren(fragment);
 }
 
 async function decryptPage() {
@@ -129,22 +136,185 @@ function hitRate(bloom_filter, words) {
   return num_hit_word / words.size;
 }
 
-function search(query) {
-  if (!query) return [];
-
-  const words = new Set(
-    segment(query).flatMap((w) => (w.trim() ? w.trim().toLowerCase() : [])),
-  );
-
+// Bloom filter による検索
+// Set<string> -> [{ index, score }]
+function bloomSearch(words) {
   return BLOOM_FILTER.flatMap((bf, i) => {
     const r = hitRate(bf, words);
     if (r == 0) return [];
-    return {
-      title: METADATA[i].title,
-      path: METADATA[i].path,
-      rate: r,
-    };
-  }).sort((a, b) => b.rate - a.rate);
+    return { index: i, score: r };
+  });
+}
+
+// 単語が格納されている転置索引のシャード番号を返す
+// INFO: Rust 側の util::search_index::shard_of と同じ計算をする必要がある
+// string -> number
+function shardOf(term) {
+  const first = [...term][0] ?? "";
+  return Number(fxhash64(first) % BigInt(SEARCH_INDEX.num_shards));
+}
+
+// [文書番号の差分, 出現回数, フィールド, ...] の形の posting list を展開する
+// [number] -> [{ doc, tf, fields }]
+function decodePostings(list) {
+  const postings = [];
+  let doc = 0;
+  for (let i = 0; i < list.length; i += 3) {
+    doc += list[i];
+    postings.push({ doc: doc, tf: list[i + 1], fields: list[i + 2] });
+  }
+  return postings;
+}
+
+// BM25 による 1 つの posting のスコア
+// (number, { doc, tf, fields }) -> number
+function bm25(df, posting) {
+  const idx = SEARCH_INDEX;
+  const n = idx.doc_lens.length;
+  const idf = Math.log(1 + (n - df + 0.5) / (df + 0.5));
+  let tf = posting.tf;
+  if (posting.fields & 1) tf += idx.title_weight;
+  if (posting.fields & 2) tf += idx.tag_weight;
+  const len = idx.doc_lens[posting.doc];
+  const norm = 1 - idx.b + (idx.b * len) / Math.max(idx.avg_doc_len, 1);
+  return (idf * tf * (idx.k1 + 1)) / (tf + idx.k1 * norm);
+}
+
+// 転置索引による検索
+// 各クエリ単語について、前方一致する単語のうち最もスコアの高いものを採用する
+// Set<string> -> [{ index, score }]
+function invertedSearch(words) {
+  const scores = new Map();
+  for (const q of words) {
+    const best = new Map();
+    const shard = SEARCH_SHARDS[shardOf(q)] ?? {};
+    for (const [term, list] of Object.entries(shard)) {
+      if (!term.startsWith(q)) continue;
+      const weight = term === q ? 1 : SEARCH_INDEX.prefix_weight;
+      const postings = decodePostings(list);
+      for (const p of postings) {
+        const s = weight * bm25(postings.length, p);
+        best.set(p.doc, Math.max(best.get(p.doc) ?? 0, s));
+      }
+    }
+    for (const [doc, s] of best) scores.set(doc, (scores.get(doc) ?? 0) + s);
+  }
+  return [...scores].map(([doc, score]) => ({ index: doc, score: score }));
+}
+
+// 単語を含む文書番号の集合を返す (転置索引)
+const termDocsCache = new Map();
+function termDocs(term) {
+  if (!termDocsCache.has(term)) {
+    const list = SEARCH_SHARDS[shardOf(term)]?.[term] ?? [];
+    termDocsCache.set(term, new Set(decodePostings(list).map((p) => p.doc)));
+  }
+  return termDocsCache.get(term);
+}
+
+// クエリの条件の評価に使う記事の情報を返す
+// number -> { tags, create, path, text, hasWord }
+function queryTarget(i) {
+  const page = langPages()[i];
+  const inverted = SEARCH_ENGINE === "inverted";
+  return {
+    tags: page.tags,
+    create: page.create,
+    path: page.path,
+    text: inverted ? searchText(i) : null,
+    hasWord: inverted
+      ? (w) => termDocs(w).has(i)
+      : (w) => hitRate(BLOOM_FILTER[i], new Set([w])) > 0,
+  };
+}
+
+// 本文を格納するシャードの番号を返す
+function textShardOf(i) {
+  return Math.floor(i / SEARCH_INDEX.docs_per_text_shard);
+}
+
+// 記事の本文を返す (読み込んでいない場合は null)
+function searchText(i) {
+  const n = SEARCH_INDEX.docs_per_text_shard;
+  return SEARCH_TEXTS[textShardOf(i)]?.[i % n] ?? null;
+}
+
+// 記事の本文のシャードのうち、 indexes の記事を含むものだけを読み込む
+function loadSearchTexts(dir, indexes) {
+  const shards = new Set(indexes.map(textShardOf));
+  return loadScripts([...shards].map((k) => `${dir}/text/${k}.js`));
+}
+
+// ([term], string) -> Promise<[{ title, path, index, score }]>
+async function search(terms, dir) {
+  if (terms.length == 0) return [];
+
+  // 単語があればスコアを計算し、なければ条件に合う記事をすべて返す
+  const pages = langPages();
+  const words = new Set(rankingWords(terms));
+  let hits;
+  if (words.size == 0) {
+    hits = pages.map((_, i) => ({ index: i, score: 0 }));
+  } else if (SEARCH_ENGINE === "inverted") {
+    hits = invertedSearch(words);
+  } else {
+    hits = bloomSearch(words);
+  }
+
+  hits = hits.filter((h) => !pages[h.index].exclude_from_search);
+
+  // フレーズの完全一致の判定に使う本文を、候補の記事の分だけ読み込む
+  const hasPhrase = terms.some((t) => t.kind === "phrase");
+  if (SEARCH_ENGINE === "inverted" && hasPhrase) {
+    await loadSearchTexts(dir, hits.map((h) => h.index));
+  }
+
+  return hits
+    .filter((h) => matchesQuery(terms, queryTarget(h.index)))
+    .sort((a, b) => b.score - a.score || a.index - b.index)
+    .map((h) => ({
+      title: pages[h.index].title,
+      path: pages[h.index].path,
+      index: h.index,
+      score: h.score,
+    }));
+}
+
+function escapeHtml(str) {
+  const div = document.createElement("div");
+  div.textContent = str;
+  return div.innerHTML;
+}
+
+// 本文のうち、最初に単語が現れる位置の前後を切り出して強調する
+// (string, Set<string>) -> string (HTML)
+function snippet(text, words) {
+  const BEFORE = 30;
+  const AFTER = 90;
+
+  const lower = text.toLowerCase();
+  const positions = [...words]
+    .map((w) => lower.indexOf(w))
+    .filter((i) => i >= 0);
+  const pos = positions.length ? Math.min(...positions) : 0;
+  const start = Math.max(0, pos - BEFORE);
+  const end = Math.min(text.length, pos + AFTER);
+  const ellipsis = (body) =>
+    `${start > 0 ? "…" : ""}${body}${end < text.length ? "…" : ""}`;
+  if (words.size == 0) return ellipsis(escapeHtml(text.slice(start, end)));
+
+  const escapeRegExp = (s) => s.replace(/[.*+?^${}()|[\]\\]/g, "\\$&");
+  const pattern = new RegExp(
+    `(${[...words].map(escapeRegExp).join("|")})`,
+    "gi",
+  );
+  const body = text
+    .slice(start, end)
+    .split(pattern)
+    .map((s, i) => (i % 2 ? `<mark>${escapeHtml(s)}</mark>` : escapeHtml(s)))
+    .join("");
+
+  return ellipsis(body);
 }
 
 function loadScriptLazily(script_path) {
@@ -166,35 +336,69 @@ function loadScriptLazily(script_path) {
   });
 }
 
-function loadScripts(scripts, callback) {
-  // すべてのスクリプトが読み込まれた後にコールバックを呼び出す
-  Promise.all(scripts.map(loadScriptLazily)).then(() => {
-    callback();
-  });
+function loadScripts(scripts) {
+  return Promise.all(scripts.map(loadScriptLazily));
+}
+
+// 転置索引のディレクトリ (ページと同じ言語のもの)
+function searchIndexDir(path_to_root) {
+  return `${path_to_root}/${langDir()}search_index`;
+}
+
+// 検索に必要なスクリプトを読み込む
+// 索引はページと同じ言語のものを使う
+// 転置索引の場合、クエリの単語が含まれるシャードだけを読み込む
+async function loadSearchIndex(query, path_to_root) {
+  await loadScripts([
+    `${path_to_root}/segmenter.js`,
+    `${path_to_root}/search_query.js`,
+  ]);
+  setDictionary(SEARCH_DICTIONARY);
+  setStemming(SEARCH_STEMMING);
+  if (SEARCH_ENGINE !== "inverted") {
+    await loadScripts([`${path_to_root}/${langDir()}bloom_filter.js`]);
+    return;
+  }
+
+  const dir = searchIndexDir(path_to_root);
+  await loadScripts([`${dir}/meta.js`]);
+  const shards = new Set(
+    parseQuery(query)
+      .filter((t) => t.kind === "word" || t.kind === "phrase")
+      .flatMap((t) => tokenize(t.value))
+      .map(shardOf),
+  );
+  await loadScripts([...shards].map((i) => `${dir}/${i}.js`));
 }
 
 let debounceTimer;
 function searchAndRender() {
   if (debounceTimer) clearTimeout(debounceTimer);
-  debounceTimer = setTimeout(() => {
+  debounceTimer = setTimeout(async () => {
+    debounceTimer = null;
     const query = document.getElementById("search-input").value;
-
-    const path_to_root =
-      document.head.querySelector('meta[name="path_to_root"]').content ?? "";
-    const segmenter_path = `${path_to_root}/segmenter.js`;
-    const filter_path = `${path_to_root}/bloom_filter.js`;
-    loadScripts([segmenter_path, filter_path], () => {
-      debounseTimer = null;
-
-      const result = search(query);
-      const html = result
-        .map((r) => {
-          const path = r.path;
-          return `<div><a href="${path_to_root}/${path}">${r.title}</a><span style="color:gray;margin-left:1em;">MatchRate:${r.rate}</span></div>`;
-        })
-        .join("");
-      document.getElementById("search-result").innerHTML = html;
-    });
+    const path_to_root = pathToRoot();
+    await loadSearchIndex(query, path_to_root);
+
+    const dir = searchIndexDir(path_to_root);
+    const terms = parseQuery(query);
+    const words = new Set(rankingWords(terms));
+    const results = await search(terms, dir);
+    // 抜粋に使う本文を、検索結果の記事の分だけ読み込む
+    if (SEARCH_ENGINE === "inverted") {
+      await loadSearchTexts(dir, results.map((r) => r.index));
+    }
+    const html = results
+      .map((r) => {
+        const link = `<a href="${path_to_root}/${pageHref(r.path)}">${r.title}</a>`;
+        if (SEARCH_ENGINE !== "inverted") {
+          return `<div>${link}<span style="color:gray;margin-left:1em;">MatchRate:${r.score}</span></div>`;
+        }
+        const text = snippet(searchText(r.index) ?? "", words);
+        return `<div>${link}<p class="search-snippet">${text}</p></div>`;
+      })
+      .join("");
+    document.getElementById("search-result").innerHTML = html;
   }, 300);
 }
 
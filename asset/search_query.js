@@ -0,0 +1,198 @@
+// 検索クエリの解析と評価
+// INFO: Rust 側の util::search_query と同じ構文・意味にする必要がある
+// 仕様は search_query_spec.json のテストケースで定める
+
+// 英単語のステミングを行うか
+let stemming = false;
+
+// tokenize で英単語のステミングを行うかを設定する
+// bool -> ()
+function setStemming(enabled) {
+  stemming = enabled;
+}
+
+// 検索用に文字列を正規化する (全角英数字や半角カナの幅を揃え、小文字に統一する)
+// INFO: Rust 側の util::normalize と同じ正規化にする必要がある
+// string -> string
+function normalize(text) {
+  return text.normalize("NFKC").toLowerCase();
+}
+
+// 英単語の複数形を単数形に揃える (Harman の S-stemmer)
+// INFO: Rust 側の util::stem と同じ規則にする必要がある
+// string -> string
+function stem(word) {
+  if (word.length < 4 || !/^[a-z]+$/.test(word)) return word;
+  const endsWithAny = (suffixes) => suffixes.some((s) => word.endsWith(s));
+  if (word.endsWith("ies")) {
+    if (!endsWithAny(["eies", "aies"])) return `${word.slice(0, -3)}y`;
+  } else if (word.endsWith("es")) {
+    if (!endsWithAny(["aes", "ees", "oes"])) return word.slice(0, -1);
+  } else if (word.endsWith("s")) {
+    if (!endsWithAny(["us", "ss"])) return word.slice(0, -1);
+  }
+  return word;
+}
+
+// 分かち書きのモデルが対応しておらず、 bigram に分割する文字か
+// INFO: Rust 側の util::is_bigram_char と同じ範囲にする必要がある
+// string -> bool
+function isBigramChar(c) {
+  const cp = c.codePointAt(0);
+  return (
+    (0x1100 <= cp && cp <= 0x11ff) || // ハングル字母
+    (0x3130 <= cp && cp <= 0x318f) || // ハングル互換字母
+    (0xac00 <= cp && cp <= 0xd7af) || // ハングル音節
+    (0x3100 <= cp && cp <= 0x312f) || // 注音符号
+    (0x31a0 <= cp && cp <= 0x31bf) ||
+    (0x3400 <= cp && cp <= 0x4dbf) || // CJK 統合漢字拡張 A
+    (0x9fa1 <= cp && cp <= 0x9fff) || // CJK 統合漢字のうち、 龠 より後のもの
+    (0x20000 <= cp && cp <= 0x3ffff) // CJK 統合漢字拡張 B 以降
+  );
+}
+
+// 文字列を重なりのある 2 文字ずつに分割する (1 文字の場合はそのまま返す)
+// string -> [string]
+function bigrams(text) {
+  const chars = [...text];
+  if (chars.length <= 1) return [text];
+  return chars.slice(1).map((c, i) => chars[i] + c);
+}
+
+// 検索用に文字列を単語に分割する
+// INFO: Rust 側の util::tokenize と同じ分割方法にする必要がある
+// string -> [string]
+function tokenize(text) {
+  // bigram に分割する文字の並びと、それ以外の並びに分ける
+  const runs = [];
+  for (const c of normalize(text)) {
+    const bigram = isBigramChar(c);
+    const last = runs[runs.length - 1];
+    if (last && last.bigram === bigram) last.text += c;
+    else runs.push({ text: c, bigram: bigram });
+  }
+
+  return runs.flatMap((run) => {
+    if (run.bigram) return bigrams(run.text);
+    return segment(run.text).flatMap((w) => {
+      w = w.trim();
+      if (!w) return [];
+      return stemming ? stem(w) : w;
+    });
+  });
+}
+
+// after: の値 (YYYY, YYYY-MM, YYYY-MM-DD) を、作成日と文字列で比較できるよう 0 埋めする
+// 月と日は 1 桁でもよい。日付として解釈できない場合は null を返す
+// INFO: Rust 側の after_date と同じ規則にする必要がある
+// string -> string | null
+function afterDate(value) {
+  const parts = value.split("-");
+  if (parts.length > 3 || !/^[0-9]{4}$/.test(parts[0])) return null;
+  let date = parts[0];
+  for (const [part, max] of parts.slice(1).map((p, i) => [p, [12, 31][i]])) {
+    if (!/^[0-9]{1,2}$/.test(part)) return null;
+    const n = Number(part);
+    if (n < 1 || n > max) return null;
+    date += `-${String(n).padStart(2, "0")}`;
+  }
+  return date;
+}
+
+// 検索クエリを解析する
+// 解釈できない条件 (例: foo:bar, after:yesterday, after:2024-13) は単語として扱う
+// クエリは索引と同じ方法で正規化してから解析する
+// string -> [{ kind, value, negated }]
+function parseQuery(query) {
+  const chars = [...normalize(query)];
+  let i = 0;
+  const isSpace = (c) => /\s/.test(c);
+
+  // 閉じ引用符 (または末尾) までを読む
+  const readQuoted = () => {
+    i += 1;
+    let s = "";
+    while (i < chars.length && chars[i] !== '"') s += chars[i++];
+    i += 1;
+    return s.split(/\s+/).filter((w) => w).join(" ");
+  };
+
+  const terms = [];
+  while (true) {
+    while (i < chars.length && isSpace(chars[i])) i += 1;
+    if (i >= chars.length) break;
+
+    const negated = chars[i] === "-";
+    if (negated) i += 1;
+
+    let head = "";
+    while (i < chars.length && !isSpace(chars[i]) && chars[i] !== '"') {
+      head += chars[i++];
+    }
+
+    const colon = head.indexOf(":");
+    const key = colon >= 0 ? head.slice(0, colon) : null;
+    const v = colon >= 0 ? head.slice(colon + 1) : null;
+    const quoted = chars[i] === '"';
+
+    let kind, value;
+    if ((key === "tag" || key === "section") && v === "" && quoted) {
+      [kind, value] = [key, readQuoted()];
+    } else if (key === "tag" && v !== "") {
+      [kind, value] = ["tag", v];
+    } else if (key === "section" && v.replace(/^\/+|\/+$/g, "") !== "") {
+      [kind, value] = ["section", v.replace(/^\/+|\/+$/g, "")];
+    } else if (key === "after" && afterDate(v) !== null) {
+      [kind, value] = ["after", afterDate(v)];
+    } else if (head === "" && quoted) {
+      [kind, value] = ["phrase", readQuoted()];
+    } else {
+      [kind, value] = ["word", head];
+    }
+
+    if (value === "") continue;
+    terms.push({ kind: kind, value: value, negated: negated });
+  }
+
+  return terms;
+}
+
+// ランキングに使う単語 (否定されていない単語とフレーズに含まれる単語) を返す
+// [term] -> [string]
+function rankingWords(terms) {
+  return terms
+    .filter((t) => !t.negated && (t.kind === "word" || t.kind === "phrase"))
+    .flatMap((t) => tokenize(t.value));
+}
+
+// 記事がすべての条件を満たすかを返す
+// 否定されていない単語はランキングにのみ使うため、常に条件を満たすものとする
+// doc: { tags, create, path, text (なければ null), hasWord }
+// ([term], object) -> bool
+function matchesQuery(terms, doc) {
+  const hasAllWords = (s) => tokenize(s).every(doc.hasWord);
+  return terms.every((t) => {
+    let matched;
+    switch (t.kind) {
+      case "word":
+        if (!t.negated) return true;
+        matched = hasAllWords(t.value);
+        break;
+      case "phrase":
+        matched = doc.text
+          ? normalize(doc.text).includes(t.value)
+          : hasAllWords(t.value);
+        break;
+      case "tag":
+        matched = doc.tags.some((tag) => normalize(tag) === t.value);
+        break;
+      case "after":
+        matched = doc.create >= t.value;
+        break;
+      case "section":
+        matched = normalize(doc.path).startsWith(`${t.value}/`);
+        break;
+    }
+    return matched !== t.negated;
+  });
+}
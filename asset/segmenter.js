@@ -74,14 +74,15 @@ const ctype_ = (str) => {
 
 const ts = (v) => v ? v : 0;
 
-const segment = (input) => {
+const segmentByModel = (input) => {
   if (input == null || input == undefined || input == "") {
     return [];
   }
   const result = [];
   const seg = ["B3","B2","B1"];
   const ctype = ["O","O","O"];
-  const o = input.split("");
+  // INFO: サロゲートペアを分けないよう、コードポイント単位で分割する
+  const o = [...input];
   for (const i of o) {
     seg.push(i);
     ctype.push(ctype_(i))
@@ -168,4 +169,52 @@ const segment = (input) => {
   return result;
 };
 
+// 分割せずに 1 つの単語として扱う語の辞書 (ユーザー辞書)
+// INFO: Rust 側の util::segmenter::Dictionary と同じ方法で照合する必要がある
+// 先頭の文字 -> 語の配列 (長い順)
+let dictionary = new Map();
+
+// 辞書を設定する
+// 検索用の単語は正規化した文字列から切り出すため、語も正規化する
+// (normalize は search_query.js で定義する)
+// [string] -> ()
+const setDictionary = (terms) => {
+  dictionary = new Map();
+  const normalized = terms.map((t) => normalize(t).trim()).filter((t) => t);
+  for (const term of new Set(normalized)) {
+    const first = [...term][0];
+    if (!dictionary.has(first)) dictionary.set(first, []);
+    dictionary.get(first).push(term);
+  }
+  const byteLength = (t) => new TextEncoder().encode(t).length;
+  for (const ts of dictionary.values()) {
+    ts.sort((a, b) => byteLength(b) - byteLength(a));
+  }
+};
+
+// 先頭から順に、辞書の語のうち最も長く一致するものを 1 つの単語として切り出し、
+// 残りの部分をモデルで分割する
+// string -> [string]
+const segment = (input) => {
+  if (input == null || input == undefined || input == "") {
+    return [];
+  }
+  const result = [];
+  let restStart = 0;
+  let i = 0;
+  while (i < input.length) {
+    const first = String.fromCodePoint(input.codePointAt(i));
+    const term = (dictionary.get(first) ?? []).find((t) => input.startsWith(t, i));
+    if (term) {
+      result.push(...segmentByModel(input.slice(restStart, i)), term);
+      i += term.length;
+      restStart = i;
+    } else {
+      i += first.length;
+    }
+  }
+  result.push(...segmentByModel(input.slice(restStart)));
+  return result;
+};
+
 // export const TinySegmenter = { segment };
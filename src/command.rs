@@ -1,11 +1,17 @@
 mod build;
 mod clean;
+mod config;
 mod init;
+mod search;
+mod stats;
+mod touch;
 
+use crate::config::{Schedule, SearchEngine};
 use crate::util::PathExt;
 use anyhow::{Result, bail};
+use chrono::NaiveDate;
 use clap::Subcommand;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[derive(PartialEq, Eq, Debug, Subcommand)]
 pub enum Command {
@@ -13,16 +19,95 @@ pub enum Command {
     Build {
         #[arg(short = 'd', long)]
         render_draft: bool,
+        /// 公開日 (publish) が未来の記事も変換する
+        #[arg(long)]
+        render_future: bool,
+        /// 公開期限 (expire) を過ぎた記事も変換する
+        #[arg(long)]
+        render_expired: bool,
+        /// 公開日と公開期限の判定に使う日付 (yyyy-MM-dd) (省略時は今日)
+        #[arg(long)]
+        now: Option<NaiveDate>,
     },
     Clean,
+    /// サイト内検索の結果を表示する
+    Search {
+        /// 検索クエリ (ブラウザの検索ボックスと同じ構文)
+        /// オプションはクエリより前に指定する
+        #[arg(required = true, allow_hyphen_values = true, trailing_var_arg = true)]
+        query: Vec<String>,
+        /// 下書きも検索対象にする
+        #[arg(short = 'd', long)]
+        render_draft: bool,
+        /// 検索方式 (省略時は zakki.toml の設定に従う)
+        #[arg(long)]
+        engine: Option<SearchEngine>,
+        /// 各記事が検索結果に含まれる (含まれない) 理由を表示する
+        #[arg(short = 'e', long)]
+        explain: bool,
+        /// 検索する記事の言語 (省略時はサイトの既定の言語)
+        #[arg(long)]
+        lang: Option<String>,
+    },
+    /// 記事の yaml ヘッダの update を今日の日付に書き換える
+    Touch {
+        /// 対象の Markdown ファイル (省略時は git でコミットされていない変更がある記事)
+        files: Vec<PathBuf>,
+    },
+    /// サイトの統計情報を表示する
+    Stats {
+        /// サイト内検索の索引の統計情報も表示する
+        #[arg(long)]
+        search: bool,
+        /// 下書きも対象にする
+        #[arg(short = 'd', long)]
+        render_draft: bool,
+    },
+    /// zakki.toml を検証する
+    Config {
+        /// 既定値を補った設定を表示する
+        #[arg(long)]
+        show: bool,
+    },
 }
 
 impl Command {
     pub fn exec(&self) -> Result<()> {
         match &self {
             Self::Init => init::init(),
-            Self::Build { render_draft } => build::build(*render_draft),
+            Self::Build {
+                render_draft,
+                render_future,
+                render_expired,
+                now,
+            } => {
+                let schedule = Schedule {
+                    now: *now,
+                    render_future: *render_future,
+                    render_expired: *render_expired,
+                };
+                build::build(*render_draft, schedule)
+            }
             Self::Clean => clean::clean(),
+            Self::Search {
+                query,
+                render_draft,
+                engine,
+                explain,
+                lang,
+            } => search::search(
+                &query.join(" "),
+                *render_draft,
+                *engine,
+                *explain,
+                lang.as_deref(),
+            ),
+            Self::Touch { files } => touch::touch(files),
+            Self::Stats {
+                search,
+                render_draft,
+            } => stats::stats(*search, *render_draft),
+            Self::Config { show } => config::config(*show),
         }
     }
 }
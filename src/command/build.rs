@@ -2,50 +2,200 @@ mod renderer;
 
 use super::clean::clean;
 use super::goto_zakki_root;
-use crate::config::FileConfig;
-use crate::util::PathExt as _;
+use crate::config::{FileConfig, Locale, Schedule, SearchEngine, UrlStyle};
+use crate::util::{
+    DOCS_PER_TEXT_SHARD, PathExt as _, SearchDocument, SearchIndex, Tokenizer, escape_html,
+};
 use crate::{config::Config, util::write_file};
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use rayon::prelude::*;
-use renderer::Renderer;
-use renderer::context::Metadata;
-use std::path::PathBuf;
+use renderer::navigation::navigations;
+use renderer::{ASSET_FILES, RenderedPage, Renderer};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
-fn render_pages(cfg: &Config) -> Result<Vec<Metadata>> {
-    let renderer = Renderer::new(cfg);
+pub(super) use renderer::context::Metadata;
+
+/// zakki.toml を読み込み、設定を作成します。
+/// カレントディレクトリは zakki のルートに移動します。
+pub(super) fn load_config(render_draft: bool) -> Result<Config> {
+    goto_zakki_root()?;
+    let file_cfg = FileConfig::load()?;
+    let pwd = std::env::current_dir()?;
+    let cfg = Config::new(file_cfg, render_draft, pwd.join("src"), pwd.join("build"));
+    Ok(cfg)
+}
+
+/// metadata.js での記事の順番 (更新日の新しい順) に並べます。
+/// 検索の索引の文書番号はこの順番に従います。
+pub(super) fn sort_metadatas(metas: &mut [Metadata]) {
+    metas.sort_unstable_by(|a, b| b.update().cmp(a.update()));
+}
+
+/// ファイルを書き出さずに全記事を描画し、メタデータを metadata.js と同じ順番で返します。
+pub(super) fn collect_metadatas(cfg: &Config) -> Result<Vec<Metadata>> {
+    let renderer = Renderer::new(cfg)?;
+    let files = cfg.src_dir().descendants_file_paths()?;
+    let mut metas: Vec<Metadata> = files
+        .par_iter()
+        .filter(|p| cfg.is_page(p))
+        .map(|p| -> Result<Option<Metadata>> {
+            let page = renderer
+                .render(p)
+                .with_context(|| p.to_string_lossy().to_string())?;
+            Ok(page.map(|p| p.meta))
+        })
+        .collect::<Result<Vec<Option<Metadata>>>>()?
+        .into_iter()
+        .flatten()
+        .collect();
+    sort_metadatas(&mut metas);
+    Ok(metas)
+}
+
+fn render_pages(cfg: &Config, renderer: &Renderer) -> Result<Vec<Metadata>> {
     renderer.render_assets()?;
 
     let files = cfg.src_dir().descendants_file_paths()?;
-    let metadatas: Vec<Metadata> = files
+    let pages: Vec<RenderedPage> = files
         .par_iter()
-        .map(|p: &PathBuf| -> Result<Option<Metadata>> {
+        .map(|p: &PathBuf| -> Result<Option<RenderedPage>> {
             renderer
                 .render(p)
                 .with_context(|| p.to_string_lossy().to_string())
         })
-        .collect::<Result<Vec<Option<Metadata>>>>()?
+        .collect::<Result<Vec<Option<RenderedPage>>>>()?
         .into_iter()
         .flatten()
         .collect();
 
-    Ok(metadatas)
+    check_output_conflicts(cfg, &files, &pages)?;
+
+    // 他の記事の情報が必要な部分を埋め込んで書き出す
+    let metas: Vec<&Metadata> = pages.iter().map(|p| &p.meta).collect();
+    let navigations = navigations(&metas);
+    let translations = translation_groups(cfg, &metas);
+    pages
+        .par_iter()
+        .zip(navigations)
+        .try_for_each(|(page, nav)| {
+            let group = &translations[page.meta.translation_key()];
+            renderer
+                .write_page(page, &nav, group)
+                .with_context(|| page.meta.path().to_string_lossy().to_string())
+        })?;
+    renderer.render_link_graph(&metas)?;
+
+    Ok(pages.into_iter().map(|p| p.meta).collect())
+}
+
+/// 記事を翻訳のグループごとにまとめます。
+/// 各グループの記事は、設定の言語の順に並べます。
+fn translation_groups<'a>(
+    cfg: &Config,
+    metas: &[&'a Metadata],
+) -> HashMap<&'a PathBuf, Vec<&'a Metadata>> {
+    let order = |m: &Metadata| cfg.locales().iter().position(|l| l.lang == m.lang());
+    let mut groups: HashMap<_, Vec<_>> = HashMap::new();
+    for m in metas {
+        groups.entry(m.translation_key()).or_default().push(*m);
+    }
+    groups
+        .values_mut()
+        .for_each(|g| g.sort_by_key(|m| (order(m), m.path())));
+    groups
+}
+
+/// zakki が生成するファイル (ルートからの相対パス)
+/// 組み込みのファイル (`ASSET_FILES`) は別に扱います。
+const GENERATED_FILES: &[&str] = &[
+    "graph.html",
+    "metadata.js",
+    "link_graph.json",
+    "link_graph.js",
+    "graph.js",
+    "sitemap.xml",
+];
+
+/// zakki が言語ごとに生成するファイル (言語のディレクトリからの相対パス)
+const GENERATED_FILES_PER_LANGUAGE: &[&str] = &["index.html", "feed.xml"];
+
+/// 出力先のパスが重複していないか確かめます。
+/// 各言語の記事一覧 (`page/`) とアーカイブ (`archive/`) のディレクトリは zakki が使うため、記事を出力できません。
+/// 転置索引の場合は、各言語の `search_index/` にはファイルを出力できません。
+fn check_output_conflicts(cfg: &Config, files: &[PathBuf], pages: &[RenderedPage]) -> Result<()> {
+    // 検索の索引のファイルは、検索方式によって異なる
+    let (search_files, search_dirs): (&[&str], &[&str]) = match cfg.search_engine() {
+        SearchEngine::Bloom => (&["bloom_filter.js"], &[]),
+        SearchEngine::Inverted => (&[], &["search_index"]),
+    };
+    let per_language = cfg.locales().iter().flat_map(|l| {
+        GENERATED_FILES_PER_LANGUAGE
+            .iter()
+            .chain(search_files)
+            .map(|f| l.dir.join(f))
+    });
+    let generated = GENERATED_FILES
+        .iter()
+        .chain(ASSET_FILES)
+        .map(PathBuf::from)
+        .chain(per_language)
+        .map(|f| (f, "zakki".to_owned()));
+    let reserved_dirs: Vec<_> = cfg
+        .locales()
+        .iter()
+        .flat_map(|l| ["page", "archive"].map(|d| l.dir.join(d)))
+        .collect();
+    let generated_dirs: Vec<_> = cfg
+        .locales()
+        .iter()
+        .flat_map(|l| search_dirs.iter().map(|d| l.dir.join(d)))
+        .collect();
+    let copied = files.iter().filter(|p| !cfg.is_page(p)).map(|p| {
+        let dst = cfg.dst_path_of(p);
+        let dst = dst.strip_prefix(cfg.dst_dir()).unwrap().to_owned();
+        let src = p.strip_prefix(cfg.src_dir()).unwrap();
+        (dst, src.display().to_string())
+    });
+    let rendered = pages.iter().flat_map(RenderedPage::outputs);
+
+    let mut outputs: HashMap<PathBuf, String> = HashMap::new();
+    let mut conflicts = Vec::new();
+    for (path, by) in generated.chain(copied).chain(rendered) {
+        let is_reserved = reserved_dirs.iter().any(|d| path.starts_with(d));
+        let is_generated = generated_dirs.iter().any(|d| path.starts_with(d));
+        if (is_reserved && path.extension_is("html")) || is_generated {
+            conflicts.push(format!("{}: reserved by zakki ({by})", path.display()));
+        } else if let Some(other) = outputs.get(&path) {
+            conflicts.push(format!("{}: {other} and {by}", path.display()));
+        } else {
+            outputs.insert(path, by);
+        }
+    }
+
+    if !conflicts.is_empty() {
+        bail!("Conflicting output paths:\n  {}", conflicts.join("\n  "));
+    }
+    Ok(())
 }
 
 fn output_sitemap(cfg: &Config, metas: &[Metadata]) -> Result<()> {
-    let Some(publish_url) = cfg.publis_url() else {
+    if cfg.publis_url().is_none() {
         return Ok(());
-    };
-    let slash = if publish_url.ends_with('/') { "" } else { "/" };
+    }
 
     let mut content = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n".to_owned();
     content += "<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n";
 
-    let is_plane = |m: &Metadata| -> bool { !m.path().starts_with("private") };
-    metas.iter().filter(|m| is_plane(m)).for_each(|m| {
+    // 下書きは、設定で指定した場合のみ含める
+    let metas = metas
+        .iter()
+        .filter(|m| m.is_listed() && !m.is_private() && (!m.is_draft() || cfg.preview().sitemap));
+    metas.for_each(|m| {
         content += &format!(
-            "  <url><loc>{publish_url}{slash}{}</loc><lastmod>{}</lastmod></url>\n",
-            &m.path().to_str().unwrap(),
-            m.update(),
+            "  <url><loc>{}</loc><lastmod>{}</lastmod></url>\n",
+            escape_html(&cfg.page_url(m.path()).unwrap()),
+            m.update().to_rfc3339(),
         );
     });
     content += "</urlset>\n";
@@ -56,35 +206,186 @@ fn output_sitemap(cfg: &Config, metas: &[Metadata]) -> Result<()> {
     Ok(())
 }
 
+/// 言語ごとのフィードを出力します。
+/// 既定の言語以外のフィードは、言語コードのディレクトリ (`en/feed.xml` など) に出力します。
+fn output_feeds(cfg: &Config, metas: &[Metadata]) -> Result<()> {
+    cfg.locales()
+        .iter()
+        .try_for_each(|locale| output_feed(cfg, locale, metas))
+}
+
+fn output_feed(cfg: &Config, locale: &Locale, metas: &[Metadata]) -> Result<()> {
+    const MAX_ENTRIES: usize = 20;
+
+    let Some(site_url) = cfg.page_url(locale.dir.join("")) else {
+        return Ok(());
+    };
+    let site_url = escape_html(&site_url);
+    let feed_path = locale.dir.join("feed.xml");
+
+    // 下書きは、設定で指定した場合のみ含める
+    let mut entries: Vec<_> = metas
+        .iter()
+        .filter(|m| m.lang() == locale.lang)
+        .filter(|m| m.is_listed() && !m.is_private() && (!m.is_draft() || cfg.preview().feed))
+        .collect();
+    entries.sort_unstable_by(|a, b| b.update().cmp(a.update()));
+    entries.truncate(MAX_ENTRIES);
+
+    let updated = entries
+        .first()
+        .map(|m| m.update().to_rfc3339())
+        .unwrap_or("1970-01-01T00:00:00Z".to_owned());
+
+    let mut content = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n".to_owned();
+    content += &format!(
+        "<feed xmlns=\"http://www.w3.org/2005/Atom\" xml:lang=\"{}\">\n",
+        locale.lang
+    );
+    content += &format!("  <title>{}</title>\n", escape_html(cfg.site_name()));
+    content += &format!("  <link href=\"{site_url}\" />\n");
+    content += &format!(
+        "  <link rel=\"self\" href=\"{}\" />\n",
+        escape_html(&cfg.page_url(&feed_path).unwrap())
+    );
+    content += &format!("  <id>{site_url}</id>\n");
+    content += &format!("  <updated>{updated}</updated>\n");
+
+    entries.iter().for_each(|m| {
+        let url = escape_html(&cfg.page_url(m.path()).unwrap());
+        content += "  <entry>\n";
+        content += &format!("    <title>{}</title>\n", escape_html(m.title()));
+        content += &format!("    <link href=\"{url}\" />\n");
+        content += &format!("    <id>{url}</id>\n");
+        content += &format!("    <published>{}</published>\n", m.create().to_rfc3339());
+        content += &format!("    <updated>{}</updated>\n", m.update().to_rfc3339());
+        if let Some(description) = m.description() {
+            content += &format!("    <summary>{}</summary>\n", escape_html(description));
+        }
+        content += "  </entry>\n";
+    });
+    content += "</feed>\n";
+
+    let dst = cfg.dst_dir().join(feed_path);
+    write_file(dst, content)?;
+
+    Ok(())
+}
+
 fn output_metadatas(cfg: &Config, mut metas: Vec<Metadata>) -> Result<()> {
     // メタデータの書き出し
-    metas.sort_unstable_by(|a, b| b.update().cmp(a.update()));
+    sort_metadatas(&mut metas);
     let js = serde_json::to_string(&metas)?;
-    let content = format!("const METADATA={js}");
+    let engine = cfg.search_engine().as_str();
+    let dictionary = serde_json::to_string(cfg.dictionary())?;
+    let stemming = cfg.search_stemming();
+    let pretty = cfg.url_style() == UrlStyle::Pretty;
+    let language = &cfg.default_locale().lang;
+    let content = format!(
+        "const METADATA={js}\nconst SEARCH_ENGINE=\"{engine}\"\nconst SEARCH_DICTIONARY={dictionary}\nconst SEARCH_STEMMING={stemming}\nconst PRETTY_URLS={pretty}\nconst SITE_LANGUAGE=\"{language}\""
+    );
     let dst = cfg.dst_dir().join("metadata.js");
     write_file(dst, content)?;
 
-    // Bloom filter の書き出し
-    let bloom: Vec<_> = metas.iter_mut().map(|e| e.bloom_filter()).collect();
+    // 検索の索引は言語ごとに作る (文書番号は metadata.js での同じ言語の記事の順番と一致する)
+    for locale in cfg.locales() {
+        let (same_lang, rest) = metas.into_iter().partition(|m| m.lang() == locale.lang);
+        metas = rest;
+        let dir = cfg.dst_dir().join(&locale.dir);
+        match cfg.search_engine() {
+            SearchEngine::Bloom => output_bloom_filters(&dir, &same_lang)?,
+            SearchEngine::Inverted => output_search_index(&dir, &same_lang, cfg.tokenizer())?,
+        }
+    }
+    Ok(())
+}
+
+/// bloom_filter.js の内容を返します。
+pub(super) fn bloom_filter_js(metas: &[Metadata]) -> Result<String> {
+    let bloom: Vec<_> = metas.iter().map(|e| e.bloom_filter()).collect();
     let js = serde_json::to_string(&bloom)?;
-    let content = format!("const BLOOM_FILTER={js}");
-    let dst = cfg.dst_dir().join("bloom_filter.js");
-    write_file(dst, content)?;
+    Ok(format!("const BLOOM_FILTER={js}"))
+}
+
+/// `dir` は出力先の言語のディレクトリです。
+fn output_bloom_filters(dir: &Path, metas: &[Metadata]) -> Result<()> {
+    let dst = dir.join("bloom_filter.js");
+    write_file(dst, bloom_filter_js(metas)?)?;
 
     Ok(())
 }
 
-pub fn build(render_draft: bool) -> Result<()> {
-    goto_zakki_root()?;
-    let file_cfg = FileConfig::load()?;
-    let pwd = std::env::current_dir()?;
-    let cfg = Config::new(file_cfg, render_draft, pwd.join("src"), pwd.join("build"));
+/// 記事の転置索引を作成します。
+/// 文書番号は metadata.js での記事の順番と一致します。
+pub(super) fn search_index(metas: &[Metadata], tokenizer: &Tokenizer) -> SearchIndex {
+    let docs = metas.iter().map(|m| SearchDocument {
+        title: m.title(),
+        tags: m.tags(),
+        text: m.text(),
+    });
+    SearchIndex::new(docs, tokenizer)
+}
+
+/// 転置索引のファイルを (search_index/ からの相対パス, 内容) の列で返します。
+pub(super) fn search_index_files(
+    metas: &[Metadata],
+    tokenizer: &Tokenizer,
+) -> Result<Vec<(String, String)>> {
+    let index = search_index(metas, tokenizer);
+
+    let js = serde_json::to_string(&index.meta())?;
+    let mut files = vec![(
+        "meta.js".to_owned(),
+        format!("const SEARCH_INDEX={js}\nconst SEARCH_SHARDS={{}}\nconst SEARCH_TEXTS={{}}"),
+    )];
+
+    for (i, shard) in index.shards().iter().enumerate() {
+        let js = serde_json::to_string(shard)?;
+        files.push((format!("{i}.js"), format!("SEARCH_SHARDS[{i}]={js}")));
+    }
+
+    // 検索結果の抜粋とフレーズの完全一致の判定に使う
+    // 候補の記事の分だけ読み込めるよう、文書番号の順に分割する
+    for (i, texts) in metas.chunks(DOCS_PER_TEXT_SHARD).enumerate() {
+        let texts: Vec<_> = texts.iter().map(|m| m.text()).collect();
+        let js = serde_json::to_string(&texts)?;
+        files.push((format!("text/{i}.js"), format!("SEARCH_TEXTS[{i}]={js}")));
+    }
+
+    Ok(files)
+}
+
+/// `dir` は出力先の言語のディレクトリです。
+fn output_search_index(dir: &Path, metas: &[Metadata], tokenizer: &Tokenizer) -> Result<()> {
+    let dir = dir.join("search_index");
+    search_index_files(metas, tokenizer)?
+        .into_par_iter()
+        .try_for_each(|(path, content)| write_file(dir.join(path), content))?;
+
+    Ok(())
+}
+
+pub fn build(render_draft: bool, schedule: Schedule) -> Result<()> {
+    let mut cfg = load_config(render_draft)?;
+    cfg.set_schedule(schedule);
 
     clean()?;
 
-    let metadatas = render_pages(&cfg)?;
+    let renderer = Renderer::new(&cfg)?;
+    let metadatas = render_pages(&cfg, &renderer)?;
+    renderer.render_listings(&metadatas)?;
     output_sitemap(&cfg, &metadatas)?;
+    output_feeds(&cfg, &metadatas)?;
     output_metadatas(&cfg, metadatas)?;
 
+    // 公開日・公開期限により変換しなかった記事を報告する
+    let skipped = renderer.skipped_pages();
+    if !skipped.is_empty() {
+        println!("skipped {} pages (now: {}):", skipped.len(), cfg.now());
+    }
+    for (path, reason) in skipped {
+        println!("  {}: {reason}", path.display());
+    }
+
     Ok(())
 }
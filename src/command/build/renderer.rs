@@ -1,36 +1,451 @@
 pub mod context;
+mod history;
 mod html_template;
+pub mod navigation;
 mod pass;
+mod permalink;
 
 use crate::copy_asset;
-use crate::util::{BloomFilter, PathExt as _};
+use crate::util::{
+    BloomFilter, PageDate, PathExt as _, Revision, escape_html, git_commit_dates, git_is_modified,
+    git_revisions, pretty_dst,
+};
 use crate::{
-    config::Config,
+    config::{Config, DatesFrom, Locale, Schedule, UrlStyle},
     util::{copy_file, encode_with_password, write_file},
 };
-use anyhow::{Context as _, Result, anyhow};
+use anyhow::{Context as _, Result, anyhow, bail};
 use base64::{Engine, prelude::BASE64_STANDARD};
+use chrono::{DateTime, Utc};
 use context::{Context, Metadata};
-use html_template::{crypto_html, index_html, page_html};
+use history::{history_body, history_link_html, history_path_of};
+use html_template::{
+    ALTERNATES_MARKER, ArchiveYear, NAVIGATION_MARKER, SocialMeta, TRANSLATIONS_MARKER,
+    alternates_html, archive_html, archive_links_html, cards_html, crypto_html, graph_html,
+    index_html, page_html, pagination_html, redirect_html, tag_list_html, translations_html,
+};
 use itertools::Itertools;
+use navigation::{LinkGraph, Navigation};
 use pass::{
-    PassManager, assign_header_id, convert_math_pass, get_title_pass, highlight_code_pass,
-    image_convert_pass, link_adjust_pass, read_header_pass, table_wrapper_pass, toc_pass,
+    PassManager, Toc, assign_header_id, convert_math_pass, excerpt_pass, get_title_pass,
+    highlight_code_pass, image_convert_pass, link_adjust_pass, read_header_pass, search_text_pass,
+    table_wrapper_pass, toc_pass,
 };
-use pulldown_cmark::{Event, Options, Parser};
-use scraper::{Html, Selector};
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::Read as _;
+use permalink::{PermalinkVars, expand_permalink, resolve_output_path, uses_date};
+use pulldown_cmark::{Event, Options, Parser, TagEnd};
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// 描画済みで、書き出し前のページ
+pub struct RenderedPage {
+    pub meta: Metadata,
+    html: String,
+    /// 変更履歴のページの HTML (変更履歴を出力する場合のみ)
+    history: Option<String>,
+    /// `src/` からソースファイルへの相対パス
+    src: PathBuf,
+    /// 記事へ転送するページの出力先 (ルートからの相対パス)
+    aliases: Vec<PathBuf>,
+}
+
+impl RenderedPage {
+    /// ページが出力するファイル (ルートからの相対パス) と、その出力元の説明を返します。
+    pub fn outputs(&self) -> Vec<(PathBuf, String)> {
+        let src = self.src.display();
+        let mut outputs = vec![(self.meta.path().clone(), src.to_string())];
+        if self.history.is_some() {
+            let path = history_path_of(self.meta.path());
+            outputs.push((path, format!("history of {src}")));
+        }
+        outputs.extend(
+            self.aliases
+                .iter()
+                .map(|a| (a.clone(), format!("alias of {src}"))),
+        );
+        outputs
+    }
+}
+
+/// 公開日・公開期限により変換しなかった理由
+#[derive(Debug, PartialEq)]
+pub enum SkipReason {
+    /// 公開日が未来
+    Scheduled(PageDate),
+    /// 公開期限を過ぎている
+    Expired(PageDate),
+}
+
+impl SkipReason {
+    /// 現在の日時 `now` において、公開日 `publish` と公開期限 `expire` の記事を変換しない理由を返します。
+    fn of(
+        schedule: &Schedule,
+        now: PageDate,
+        publish: Option<PageDate>,
+        expire: Option<PageDate>,
+    ) -> Option<Self> {
+        if let Some(date) = publish
+            && !schedule.render_future
+            && date > now
+        {
+            return Some(Self::Scheduled(date));
+        }
+        if let Some(date) = expire
+            && !schedule.render_expired
+            && date <= now
+        {
+            return Some(Self::Expired(date));
+        }
+        None
+    }
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Scheduled(date) => write!(f, "scheduled for {date} (use --render-future)"),
+            Self::Expired(date) => write!(f, "expired on {date} (use --render-expired)"),
+        }
+    }
+}
+
+/// ルートからページへの相対パス `dst` から、ページからルートへの相対パスを返します。
+/// ルート直下のページの場合は `.` を返します。
+fn path_to_root_of(dst: &Path) -> PathBuf {
+    let path_to_root = dst.parent().unwrap().dir_path_to_origin_unchecked();
+    if path_to_root.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        path_to_root
+    }
+}
+
+/// ページバンドルの記事から参照しているファイルが、ページバンドル内に存在するか確かめます。
+/// ページバンドルの外を指すパスは確かめません。
+fn check_bundle_assets(src: &Path, ctxt: &Context) -> Result<()> {
+    let dir = src.parent().unwrap();
+    let missing: Vec<_> = ctxt
+        .assets()
+        .iter()
+        .filter(|a| !a.starts_with(".."))
+        .filter(|a| !dir.join(a).exists())
+        .map(|a| a.display().to_string())
+        .unique()
+        .collect();
+    if !missing.is_empty() {
+        bail!("Missing files in the page bundle: {}", missing.join(", "));
+    }
+    Ok(())
+}
+
+/// 組み込みのファイルの一覧 `ASSET_FILES` と、それらを出力先にコピーする `copy_assets` を定義します。
+macro_rules! asset_files {
+    ($($fname:literal),* $(,)?) => {
+        /// 出力先にコピーする組み込みのファイル (ルートからの相対パス)
+        pub const ASSET_FILES: &[&str] = &[$($fname),*];
+
+        fn copy_assets(dst_dir: &Path) -> Result<()> {
+            $(copy_asset!($fname, dst_dir)?;)*
+            Ok(())
+        }
+    };
+}
+
+asset_files!(
+    "style.css",
+    "script.js",
+    "segmenter.js",
+    "search_query.js",
+    "theme.js",
+    "katex/LICENSE",
+    "katex/katex.min.css",
+    "katex/fonts/KaTeX_AMS-Regular.woff2",
+    "katex/fonts/KaTeX_Caligraphic-Bold.woff2",
+    "katex/fonts/KaTeX_Caligraphic-Regular.woff2",
+    "katex/fonts/KaTeX_Fraktur-Bold.woff2",
+    "katex/fonts/KaTeX_Fraktur-Regular.woff2",
+    "katex/fonts/KaTeX_Main-BoldItalic.woff2",
+    "katex/fonts/KaTeX_Main-Bold.woff2",
+    "katex/fonts/KaTeX_Main-Italic.woff2",
+    "katex/fonts/KaTeX_Main-Regular.woff2",
+    "katex/fonts/KaTeX_Math-BoldItalic.woff2",
+    "katex/fonts/KaTeX_Math-Italic.woff2",
+    "katex/fonts/KaTeX_SansSerif-Bold.woff2",
+    "katex/fonts/KaTeX_SansSerif-Italic.woff2",
+    "katex/fonts/KaTeX_SansSerif-Regular.woff2",
+    "katex/fonts/KaTeX_Script-Regular.woff2",
+    "katex/fonts/KaTeX_Size1-Regular.woff2",
+    "katex/fonts/KaTeX_Size2-Regular.woff2",
+    "katex/fonts/KaTeX_Size3-Regular.woff2",
+    "katex/fonts/KaTeX_Size4-Regular.woff2",
+    "katex/fonts/KaTeX_Typewriter-Regular.woff2",
+    "font/SourceCodePro/LICENSE.md",
+    "font/SourceCodePro/SourceCodePro-Regular.otf.woff2",
+);
+
+fn markdown_options() -> Options {
+    Options::all() ^ Options::ENABLE_OLD_FOOTNOTES ^ Options::ENABLE_FOOTNOTES
+}
+
+/// Markdown をイベント列に変換します。
+fn parse_markdown(markdown: &str) -> Vec<Event<'_>> {
+    Parser::new_ext(markdown, markdown_options()).collect()
+}
+
+/// Markdown の yaml ヘッダまでをイベント列に変換します。
+fn parse_header(markdown: &str) -> Vec<Event<'_>> {
+    let mut events = Vec::new();
+    for e in Parser::new_ext(markdown, markdown_options()) {
+        let is_end = matches!(e, Event::End(TagEnd::MetadataBlock(_)));
+        events.push(e);
+        if is_end {
+            break;
+        }
+    }
+    events
+}
+
+/// yaml ヘッダを読み、作成日・更新日と出力先を求めた記事
+/// 描画の前に全記事について求め、描画時に使います。
+struct PreparedPage {
+    markdown: String,
+    ctxt: Context,
+    /// 記事へ転送するページの出力先 (ルートからの相対パス)
+    aliases: Vec<PathBuf>,
+}
 
 pub struct Renderer<'a> {
     config: &'a Config,
+    /// 全記事の、 `src/` からのソースファイルのパスとルートからの出力先のパスの対応
+    permalinks: Arc<HashMap<PathBuf, PathBuf>>,
+    /// 描画を待つ記事 (`src/` からのソースファイルのパスがキー)
+    prepared: Mutex<HashMap<PathBuf, PreparedPage>>,
+    /// 公開日・公開期限により変換しなかった記事 (ルートからの出力先への相対パスと理由)
+    skipped: Mutex<Vec<(PathBuf, SkipReason)>>,
 }
 
 impl<'a> Renderer<'a> {
-    pub fn new(config: &'a Config) -> Self {
-        Self { config }
+    /// 全記事の yaml ヘッダを読み、出力先を求めた上で作成します。
+    pub fn new(config: &'a Config) -> Result<Self> {
+        let mut renderer = Self {
+            config,
+            skipped: Mutex::default(),
+            permalinks: Arc::default(),
+            prepared: Mutex::default(),
+        };
+        let prepared = renderer.prepare_pages()?;
+        let permalinks = prepared
+            .iter()
+            .map(|(src, page)| Ok((src.clone(), page.ctxt.build_root_to_dst()?.clone())))
+            .collect::<Result<_>>()?;
+        renderer.permalinks = Arc::new(permalinks);
+        renderer.prepared = Mutex::new(prepared);
+        Ok(renderer)
+    }
+
+    /// 言語コードに対応する言語を返します。
+    /// 記事の言語は描画時に確かめているため、見つからない場合は既定の言語を返します。
+    fn locale(&self, lang: &str) -> &Locale {
+        self.config
+            .locale_of(lang)
+            .unwrap_or(self.config.default_locale())
+    }
+
+    /// 記事の言語を返します (出力先を求めた後に使えます)。
+    fn locale_of_page(&self, ctxt: &Context) -> &Locale {
+        self.locale(ctxt.lang.as_deref().unwrap_or_default())
+    }
+
+    /// 記事の描画に使う文脈を作成します。
+    fn new_context(&self, src: &Path) -> Context {
+        let mut ctxt = Context::default();
+        ctxt.timezone = self.config.timezone();
+        ctxt.link_style = self.config.link_style().clone();
+        let src_root_to_src = src.strip_prefix(self.config.src_dir()).unwrap();
+        ctxt.set_src_root_to_src(src_root_to_src.to_owned());
+        ctxt.permalinks = self.permalinks.clone();
+        ctxt
+    }
+
+    /// 全記事の yaml ヘッダを読み、 `src/` からのソースファイルのパスをキーにして返します。
+    /// 他の記事へのリンクを出力先へのリンクに変換できるよう、描画の前に出力先を求めます。
+    /// 描画しない下書きは含めません。
+    fn prepare_pages(&self) -> Result<HashMap<PathBuf, PreparedPage>> {
+        let files = self.config.src_dir().descendants_file_paths()?;
+        let pages: Vec<_> = files
+            .par_iter()
+            .filter(|p| self.config.is_page(p))
+            .map(|src| {
+                self.prepare_page(src)
+                    .with_context(|| src.to_string_lossy().to_string())
+            })
+            .collect::<Result<_>>()?;
+        Ok(pages.into_iter().flatten().collect())
+    }
+
+    /// 記事の yaml ヘッダを読み、作成日・更新日と出力先を求めます。
+    /// 下書きを描画しない設定の場合、下書きには `None` を返します。
+    fn prepare_page(&self, src: &Path) -> Result<Option<(PathBuf, PreparedPage)>> {
+        let markdown = std::fs::read_to_string(src)?;
+        let mut ctxt = self.new_context(src);
+        if let Some(password) = self.config.password() {
+            ctxt.set_password(password.clone());
+        }
+        let src_root_to_src = ctxt.src_root_to_src()?.clone();
+        let collection = self.config.collection_of(&src_root_to_src);
+        ctxt.is_draft = collection.draft();
+        ctxt.to_encrypt = collection.encrypted();
+        ctxt.is_listed = collection.listed();
+
+        read_header_pass(&mut parse_header(&markdown), &mut ctxt)?;
+        if ctxt.is_draft && !self.config.render_draft() {
+            return Ok(None);
+        }
+        self.fill_dates(src, &mut ctxt)?;
+        let (build_root_to_dst, aliases) = self.output_paths_of(src, &mut ctxt)?;
+        ctxt.set_build_root_to_dst(build_root_to_dst);
+        if !collection.indexed() {
+            ctxt.exclude_from_search = true;
+        }
+
+        let page = PreparedPage {
+            markdown,
+            ctxt,
+            aliases,
+        };
+        Ok(Some((src_root_to_src, page)))
+    }
+
+    /// yaml ヘッダと設定に従って、記事の出力先と、記事へ転送するページの出力先を返します。
+    /// パスはいずれもルートからの相対パスです。
+    fn output_paths_of(&self, src: &Path, ctxt: &mut Context) -> Result<(PathBuf, Vec<PathBuf>)> {
+        let src_root_to_src = ctxt.src_root_to_src()?.clone();
+
+        // ページバンドルの場合は、ディレクトリを記事のファイルとみなす
+        let file = if self.config.bundle_of(src).is_some() {
+            src_root_to_src.parent().unwrap()
+        } else {
+            &src_root_to_src
+        };
+        let dir = file.parent().unwrap();
+
+        // ファイル名の末尾の言語コード (`foo.en.md`) は、スラッグと翻訳のグループに含めない
+        let stem = file.file_stem().unwrap().to_str().unwrap();
+        let (base, suffix_lang) = self.config.split_lang_suffix(stem);
+        let lang = match &ctxt.lang {
+            Some(lang) if self.config.locale_of(lang).is_none() => {
+                let langs = self.config.locales().iter().map(|l| &l.lang).join(", ");
+                bail!("Unknown `lang` in yaml header: `{lang}` (available: {langs})")
+            }
+            Some(lang) => lang.clone(),
+            None => suffix_lang
+                .unwrap_or(&self.config.default_locale().lang)
+                .to_owned(),
+        };
+        ctxt.set_translation_key(self.config.translation_key_of(file));
+
+        let slug = match &ctxt.slug {
+            Some(slug) if slug.is_empty() || slug.contains(['/', '\\']) => {
+                bail!("Invalid `slug` in yaml header: `{slug}`")
+            }
+            Some(slug) => slug.clone(),
+            None => base.to_owned(),
+        };
+        // パターンの指定がない場合のファイル名は、言語コードを含める
+        let name = ctxt.slug.as_deref().unwrap_or(stem);
+
+        let collection = self.config.collection_of(&src_root_to_src);
+        let pattern = ctxt
+            .permalink
+            .clone()
+            .or_else(|| self.config.permalink_of(collection).map(str::to_owned));
+        let dst = match pattern {
+            Some(pattern) => {
+                let date = if uses_date(&pattern) {
+                    Some(ctxt.create_date()?)
+                } else {
+                    None
+                };
+                let vars = PermalinkVars {
+                    date,
+                    slug: &slug,
+                    dir,
+                    lang: &lang,
+                };
+                expand_permalink(&pattern, &vars)?
+            }
+            // パターンの指定がなければ、ソースファイルと同じ場所に出力する
+            None if file != src_root_to_src => dir.join(name).join("index.html"),
+            None => dir.join(format!("{name}.html")),
+        };
+        ctxt.lang = Some(lang);
+        let dst = match self.config.url_style() {
+            UrlStyle::File => dst,
+            UrlStyle::Pretty => pretty_dst(dst),
+        };
+
+        let aliases = ctxt
+            .aliases
+            .iter()
+            .map(|a| resolve_output_path(a, dir).context("Invalid `aliases` in yaml header"))
+            .collect::<Result<_>>()?;
+        Ok((dst, aliases))
+    }
+
+    /// 公開日・公開期限により変換しなかった記事を、パスの順に返します。
+    pub fn skipped_pages(self) -> Vec<(PathBuf, SkipReason)> {
+        let mut skipped = self.skipped.into_inner().unwrap();
+        skipped.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        skipped
+    }
+
+    /// yaml ヘッダで省略された作成日・更新日を、設定に応じて補います。
+    /// git の履歴から取得する場合、コミットされていないファイルと、コミットされていない変更がある
+    /// ファイルの更新日にはファイルの更新日時を使います。
+    fn fill_dates(&self, src: &Path, ctxt: &mut Context) -> Result<()> {
+        let has_create = ctxt.create_date().is_ok();
+        let has_update = ctxt.last_update_date().is_ok();
+        if has_create && has_update {
+            return Ok(());
+        }
+        if self.config.dates_from() != DatesFrom::Git {
+            let key = if has_create { "update" } else { "create" };
+            bail!("`{key}` is missing in yaml header.");
+        }
+
+        let tz = self.config.timezone();
+        let mtime = PageDate::from_datetime(DateTime::<Utc>::from(src.metadata()?.modified()?), tz);
+        let (create, update) = match git_commit_dates(src)? {
+            Some((first, last)) => {
+                let first = PageDate::from_datetime(first, tz);
+                let last = PageDate::from_datetime(last, tz);
+                let update = if git_is_modified(src)? {
+                    mtime.max(last)
+                } else {
+                    last
+                };
+                (first, update)
+            }
+            None => (mtime, mtime),
+        };
+        if !has_create {
+            ctxt.set_create_date(create);
+        }
+        if !has_update {
+            ctxt.set_last_update_date(update);
+        }
+        Ok(())
+    }
+
+    /// 公開日・公開期限により、記事を変換しない理由を返します。
+    fn skip_reason(&self, ctxt: &Context) -> Option<SkipReason> {
+        SkipReason::of(
+            self.config.schedule(),
+            self.config.now(),
+            ctxt.publish_date,
+            ctxt.expire_date,
+        )
     }
 
     const fn default_css_list(&self) -> [&'static str; 1] {
@@ -41,18 +456,110 @@ impl<'a> Renderer<'a> {
         ["metadata.js", "script.js", "theme.js"]
     }
 
-    fn events_to_html(&self, events: Vec<Event>, ctxt: &Context) -> Result<String> {
-        let body = {
-            let mut body = String::new();
-            pulldown_cmark::html::push_html(&mut body, events.into_iter());
-            body
+    /// ページ内の URL を公開 URL に変換します。
+    /// 公開先 URL が設定されていない場合、相対 URL は `None` になります。
+    fn absolute_url_of(&self, url: &str, ctxt: &Context) -> Result<Option<String>> {
+        if url.starts_with("http://") || url.starts_with("https://") {
+            return Ok(Some(url.to_owned()));
+        }
+
+        let path = match url.strip_prefix('/') {
+            Some(path) => PathBuf::from(path),
+            None => {
+                let dir = ctxt.build_root_to_dst()?.parent().unwrap();
+                dir.join(url).normalized()
+            }
         };
+        Ok(self.config.page_url(path))
+    }
 
-        let path_to_root = ctxt
-            .build_root_to_dst()?
-            .parent()
-            .unwrap()
-            .dir_path_to_origin_unchecked();
+    fn social_meta<'b>(
+        &'b self,
+        ctxt: &'b Context,
+        url: Option<&'b str>,
+        image: Option<&'b str>,
+    ) -> Result<SocialMeta<'b>> {
+        // 暗号化するページの内容は漏らさない
+        let (description, image) = if ctxt.to_encrypt {
+            (self.locale_of_page(ctxt).strings.protected.as_str(), None)
+        } else {
+            let description = ctxt.description().unwrap_or(ctxt.title()?);
+            (description.as_str(), image)
+        };
+
+        Ok(SocialMeta {
+            site_name: self.config.site_name(),
+            title: ctxt.title()?,
+            description,
+            url,
+            image,
+            dates: Some((ctxt.create_date()?, ctxt.last_update_date()?)),
+            noindex: ctxt.is_draft,
+        })
+    }
+
+    /// イベント列を記事のページの HTML に変換します。
+    /// `history` が真の場合、変更履歴のページへのリンクを本文の末尾に加えます。
+    fn events_to_html(&self, events: Vec<Event>, ctxt: &Context, history: bool) -> Result<String> {
+        let mut body = String::new();
+        pulldown_cmark::html::push_html(&mut body, events.into_iter());
+        if history {
+            let label = &self.locale_of_page(ctxt).strings.history;
+            body += &history_link_html(ctxt.build_root_to_dst()?, label);
+        }
+
+        let dst = ctxt.build_root_to_dst()?;
+        let template = self
+            .config
+            .collection_of(ctxt.src_root_to_src()?)
+            .template();
+        self.body_to_html(
+            &body,
+            ctxt,
+            dst,
+            ctxt.title()?,
+            ctxt.toc()?,
+            ctxt.is_draft,
+            template,
+        )
+    }
+
+    /// 変更履歴のページの HTML を生成します。
+    /// 暗号化する記事の場合、変更履歴も暗号化します。
+    fn history_to_html(&self, revisions: &[Revision], ctxt: &Context) -> Result<String> {
+        let page = ctxt.build_root_to_dst()?;
+        let label = &self.locale_of_page(ctxt).strings.history;
+        let tz = self.config.timezone();
+        let (body, toc) = history_body(
+            label,
+            ctxt.title()?,
+            page,
+            revisions,
+            tz,
+            self.config.link_style(),
+        );
+        let title = format!("{label}: {}", ctxt.title()?);
+        // 変更履歴はコレクションのテンプレートではなく、既定のテンプレートで生成する
+        let dst = history_path_of(page);
+        self.body_to_html(&body, ctxt, &dst, &title, &toc, true, None)
+    }
+
+    /// 本文をページの HTML に変換します。
+    /// `dst` はルートからページの出力先への相対パスです。
+    /// `template` が `None` の場合は既定のテンプレートを使います。
+    #[allow(clippy::too_many_arguments)]
+    fn body_to_html(
+        &self,
+        body: &str,
+        ctxt: &Context,
+        dst: &Path,
+        title: &str,
+        toc: &Toc,
+        noindex: bool,
+        template: Option<&str>,
+    ) -> Result<String> {
+        let path_to_root = path_to_root_of(dst);
+        let locale = self.locale_of_page(ctxt);
 
         let css_list = self
             .default_css_list()
@@ -66,6 +573,15 @@ impl<'a> Renderer<'a> {
             .chain(self.config.js_list().iter().map(|p| &p[..]))
             .chain(ctxt.js_list().iter().map(|p| &p[..]));
 
+        let url = self.config.page_url(dst);
+        let image = match ctxt.image() {
+            Ok(image) => self.absolute_url_of(image, ctxt)?,
+            Err(_) => None,
+        };
+        let mut social_meta = self.social_meta(ctxt, url.as_deref(), image.as_deref())?;
+        social_meta.title = title;
+        social_meta.noindex = noindex;
+
         let html = if ctxt.to_encrypt {
             let password = ctxt.password()?;
             let cypher = encode_with_password(password, body.as_bytes());
@@ -73,8 +589,10 @@ impl<'a> Renderer<'a> {
 
             crypto_html(
                 &path_to_root,
+                locale,
+                self.config.link_style(),
                 self.config.site_name(),
-                ctxt.title()?,
+                title,
                 ctxt.create_date()?,
                 ctxt.last_update_date()?,
                 css_list,
@@ -82,88 +600,81 @@ impl<'a> Renderer<'a> {
                 ctxt.tags()?,
                 &encoded,
                 self.config.footer(),
+                &social_meta,
+                ctxt.is_draft,
             )
         } else {
             page_html(
                 &path_to_root,
+                locale,
+                self.config.link_style(),
                 self.config.site_name(),
-                ctxt.title()?,
+                title,
                 ctxt.create_date()?,
                 ctxt.last_update_date()?,
                 css_list,
                 js_list,
                 ctxt.tags()?,
-                &body,
+                body,
                 self.config.footer(),
-                ctxt.toc()?,
+                toc,
+                &social_meta,
+                ctxt.is_draft,
+                template,
             )
+            .context("Failed to fill the template of the collection")?
         };
 
         Ok(html)
     }
 
-    fn make_bloom_filter(&self, html: &str) -> Result<BloomFilter> {
-        // HTML からテキストを抜き出す
-        let text = Html::parse_document(html)
-            .select(&Selector::parse("#main-content").unwrap())
-            .next()
-            .ok_or_else(|| anyhow!("No body element"))?
-            .text()
-            .join(" ");
-
-        // テキストをワードに分割する
-        let words: HashSet<_> = crate::util::segment(&text)
-            .into_iter()
-            // スペースのみの場合は無視する
-            .map(|w| w.trim())
-            .filter(|w| !w.is_empty())
-            // 小文字に統一する
-            .map(|w| w.to_lowercase())
-            .collect();
-
-        // Bloom filter を構築する
+    fn make_bloom_filter(&self, words: &HashSet<String>) -> BloomFilter {
         let fp = self.config.search_fp();
         let num_words = words.len();
         let mut filter = BloomFilter::new(num_words, fp);
         words.iter().for_each(|w| filter.insert_word(w));
 
-        Ok(filter)
+        filter
     }
 
     /// Markdown を HTML に変換します。
     /// 変換後の HTML とメタデータを返します。
     /// Markdown がドラフト記事であり、ドラフトを描画しない設定の場合は `None` を返します。
-    fn md_to_html(&self, markdown: &str, dst_path: PathBuf) -> Result<Option<(String, Metadata)>> {
-        let mut ctxt = Context::default();
-        if let Some(password) = self.config.password() {
-            ctxt.set_password(password.clone());
+    /// 公開日が未来の記事と、公開期限を過ぎた記事の場合も `None` を返します。
+    fn md_to_html(&self, src: &Path) -> Result<Option<RenderedPage>> {
+        let src_root_to_src = src.strip_prefix(self.config.src_dir())?;
+        let prepared = self.prepared.lock().unwrap().remove(src_root_to_src);
+        // yaml ヘッダを読んだ時点で除いた下書き
+        let Some(PreparedPage {
+            markdown,
+            mut ctxt,
+            aliases,
+        }) = prepared
+        else {
+            return Ok(None);
+        };
+        ctxt.permalinks = self.permalinks.clone();
+        let build_root_to_dst = ctxt.build_root_to_dst()?.clone();
+
+        if ctxt.is_draft && !self.config.preview().search {
+            ctxt.exclude_from_search = true;
         }
-        ctxt.is_draft = dst_path
-            .strip_prefix(self.config.dst_dir())
-            .unwrap()
-            .starts_with("draft/");
-        ctxt.to_encrypt = dst_path
-            .strip_prefix(self.config.dst_dir())
-            .unwrap()
-            .starts_with("private/");
-
-        let build_root_to_dst = dst_path.strip_prefix(self.config.dst_dir()).unwrap();
-        ctxt.set_build_root_to_dst(build_root_to_dst.to_owned());
-
-        // Markdown をイベント列に変換
-        let opt = Options::all() ^ Options::ENABLE_OLD_FOOTNOTES ^ Options::ENABLE_FOOTNOTES;
-        let mut events: Vec<_> = Parser::new_ext(markdown, opt).collect();
-
-        // イベント列に対してパスを適用
-        read_header_pass(&mut events, &mut ctxt)?;
-
-        if !self.config.render_draft() && ctxt.is_draft {
+
+        if let Some(reason) = self.skip_reason(&ctxt) {
+            self.skipped
+                .lock()
+                .unwrap()
+                .push((build_root_to_dst, reason));
             return Ok(None);
         }
 
+        // Markdown をイベント列に変換し、パスを適用
+        let events = parse_markdown(&markdown);
         let mut pass_manager = PassManager::new();
         pass_manager
             .register(get_title_pass)
+            .register(search_text_pass)
+            .register(excerpt_pass)
             .register(link_adjust_pass)
             .register(image_convert_pass)
             .register(highlight_code_pass)
@@ -173,110 +684,546 @@ impl<'a> Renderer<'a> {
             .register(toc_pass);
 
         let events = pass_manager.run(events, &mut ctxt)?;
+        if self.config.bundle_of(src).is_some() {
+            check_bundle_assets(src, &ctxt)?;
+        }
+
+        // 変更履歴 (git の履歴がない場合は出力しない)
+        let revisions = if ctxt.history {
+            git_revisions(src)?
+        } else {
+            vec![]
+        };
+        let history = if revisions.is_empty() {
+            None
+        } else {
+            Some(self.history_to_html(&revisions, &ctxt)?)
+        };
 
         // イベント列を HTML に変換
-        let html = self.events_to_html(events, &ctxt)?;
+        let html = self.events_to_html(events, &ctxt, history.is_some())?;
 
-        // HTML に対してパスを適用
-        let filter = self.make_bloom_filter(&html)?;
-        ctxt.set_bloom_filter(filter);
+        // 検索用の索引を作成
+        let words: HashSet<_> = self
+            .config
+            .tokenizer()
+            .tokenize(ctxt.text()?)
+            .into_iter()
+            .collect();
+        ctxt.set_bloom_filter(self.make_bloom_filter(&words));
+        ctxt.set_words(words);
 
-        Ok(Some((html, ctxt.try_into()?)))
+        let src = ctxt.src_root_to_src()?.clone();
+        Ok(Some(RenderedPage {
+            meta: ctxt.try_into()?,
+            html,
+            history,
+            src,
+            aliases,
+        }))
     }
 
-    pub fn render(&self, src: impl AsRef<Path>) -> Result<Option<Metadata>> {
+    /// ソースファイルを描画します。
+    /// Markdown 以外のファイルはそのままコピーします。
+    /// Markdown の場合、他の記事の情報を使う部分を除いて描画し、書き出しは [`Self::write_page`] で行います。
+    pub fn render(&self, src: impl AsRef<Path>) -> Result<Option<RenderedPage>> {
         let src = src.as_ref();
-        if !src.extension_is("md") {
+        if !self.config.is_page(src) {
             copy_file(src, self.config.dst_path_of(src))?;
             return Ok(None);
         }
 
-        let markdown = {
-            let mut file = File::open(src)?;
-            let mut content = String::new();
-            file.read_to_string(&mut content)?;
-            content
-        };
+        self.md_to_html(src)
+    }
 
-        let dst_path = self.config.dst_path_of(src);
-        let Some((html, meta)) = self.md_to_html(&markdown, dst_path.clone())? else {
-            return Ok(None);
+    /// 他の記事へのナビゲーションと、他の言語の記事へのリンクを埋め込んで、ページを書き出します。
+    /// `translations` は記事と同じ翻訳のグループの記事 (記事自身を含む) です。
+    pub fn write_page(
+        &self,
+        page: &RenderedPage,
+        navigation: &Navigation,
+        translations: &[&Metadata],
+    ) -> Result<()> {
+        let path_to_root = path_to_root_of(page.meta.path());
+        let locale = self.locale(page.meta.lang());
+
+        // hreflang の URL は、公開先 URL が設定されていれば絶対 URL にする
+        let href = |m: &Metadata| {
+            self.config
+                .link_style()
+                .href_of(path_to_root.join(m.path()))
         };
+        let alternates: Vec<_> = translations
+            .iter()
+            .map(|m| (m.lang(), self.config.page_url(m.path()).unwrap_or(href(m))))
+            .collect();
+        let others: Vec<_> = translations
+            .iter()
+            .filter(|m| m.lang() != page.meta.lang())
+            .map(|m| (self.locale(m.lang()), href(m)))
+            .collect();
+
+        let html = page
+            .html
+            .replace(
+                NAVIGATION_MARKER,
+                &navigation.to_html(&path_to_root, &locale.strings, self.config.link_style()),
+            )
+            .replace(
+                ALTERNATES_MARKER,
+                &alternates_html(&alternates, &self.config.default_locale().lang),
+            )
+            .replace(
+                TRANSLATIONS_MARKER,
+                &translations_html(&others, &locale.strings),
+            );
+
+        let dst = self.config.dst_dir().join(page.meta.path());
+        write_file(dst, html)?;
 
-        write_file(dst_path, html)?;
+        if let Some(history) = &page.history {
+            let dst = self
+                .config
+                .dst_dir()
+                .join(history_path_of(page.meta.path()));
+            let history = history
+                .replace(NAVIGATION_MARKER, "")
+                .replace(ALTERNATES_MARKER, "")
+                .replace(TRANSLATIONS_MARKER, "");
+            write_file(dst, history)?;
+        }
 
-        Ok(Some(meta))
+        // 転送先は、公開先 URL が設定されていれば絶対 URL、そうでなければ相対パスにする
+        for alias in &page.aliases {
+            let url = self.config.page_url(page.meta.path()).unwrap_or_else(|| {
+                let dir = alias.parent().unwrap();
+                self.config
+                    .link_style()
+                    .href_of(page.meta.path().relative_from(dir))
+            });
+            let dst = self.config.dst_dir().join(alias);
+            let html = redirect_html(page.meta.title(), &url, page.meta.lang());
+            write_file(dst, html)?;
+        }
+
+        Ok(())
     }
 
     pub fn render_assets(&self) -> Result<()> {
-        self.render_index()?;
-        copy_asset!("style.css", self.config.dst_dir())?;
-        copy_asset!("script.js", self.config.dst_dir())?;
-        copy_asset!("segmenter.js", self.config.dst_dir())?;
-        copy_asset!("theme.js", self.config.dst_dir())?;
-
-        copy_asset!("katex/LICENSE", self.config.dst_dir())?;
-        copy_asset!("katex/katex.min.css", self.config.dst_dir())?;
-
-        macro_rules! copy_katex_fonts {
-            ($($font_name:literal),* $(,)?) => {
-                $(
-                    copy_asset!(concat!("katex/fonts/", $font_name), self.config.dst_dir())?;
-                )*
-            }
+        copy_assets(self.config.dst_dir())
+    }
+
+    fn social_meta_of_listing<'b>(
+        &'b self,
+        title: &'b str,
+        url: Option<&'b str>,
+    ) -> SocialMeta<'b> {
+        SocialMeta {
+            site_name: self.config.site_name(),
+            title,
+            description: self.config.site_name(),
+            url,
+            image: None,
+            dates: None,
+            noindex: false,
         }
+    }
 
-        copy_katex_fonts!(
-            "KaTeX_AMS-Regular.woff2",
-            "KaTeX_Caligraphic-Bold.woff2",
-            "KaTeX_Caligraphic-Regular.woff2",
-            "KaTeX_Fraktur-Bold.woff2",
-            "KaTeX_Fraktur-Regular.woff2",
-            "KaTeX_Main-BoldItalic.woff2",
-            "KaTeX_Main-Bold.woff2",
-            "KaTeX_Main-Italic.woff2",
-            "KaTeX_Main-Regular.woff2",
-            "KaTeX_Math-BoldItalic.woff2",
-            "KaTeX_Math-Italic.woff2",
-            "KaTeX_SansSerif-Bold.woff2",
-            "KaTeX_SansSerif-Italic.woff2",
-            "KaTeX_SansSerif-Regular.woff2",
-            "KaTeX_Script-Regular.woff2",
-            "KaTeX_Size1-Regular.woff2",
-            "KaTeX_Size2-Regular.woff2",
-            "KaTeX_Size3-Regular.woff2",
-            "KaTeX_Size4-Regular.woff2",
-            "KaTeX_Typewriter-Regular.woff2",
-        );
+    /// 記事間のリンクグラフを出力します。
+    /// 設定で有効になっている場合、グラフを可視化するページも出力します。
+    pub fn render_link_graph(&self, metas: &[&Metadata]) -> Result<()> {
+        let graph = serde_json::to_string(&LinkGraph::new(metas))?;
+        write_file(self.config.dst_dir().join("link_graph.json"), &graph)?;
 
-        copy_asset!("font/SourceCodePro/LICENSE.md", self.config.dst_dir())?;
-        copy_asset!(
-            "font/SourceCodePro/SourceCodePro-Regular.otf.woff2",
-            self.config.dst_dir()
-        )?;
+        if !self.config.link_graph_page() {
+            return Ok(());
+        }
 
-        Ok(())
-    }
+        write_file(
+            self.config.dst_dir().join("link_graph.js"),
+            format!("const LINK_GRAPH={graph}"),
+        )?;
+        copy_asset!("graph.js", self.config.dst_dir())?;
 
-    fn render_index(&self) -> Result<()> {
         let css_list = self
             .default_css_list()
             .into_iter()
             .chain(self.config.css_list().iter().map(|p| &p[..]));
-
         let js_list = self
             .default_js_list()
             .into_iter()
+            .chain(["link_graph.js", "graph.js"])
             .chain(self.config.js_list().iter().map(|p| &p[..]));
 
-        let content = index_html(
+        let locale = self.config.default_locale();
+        let url = self.config.page_url("graph.html");
+        let social_meta = self.social_meta_of_listing(&locale.strings.link_graph, url.as_deref());
+        let content = graph_html(
+            locale,
+            self.config.link_style(),
             self.config.site_name(),
             css_list,
             js_list,
             self.config.footer(),
+            &social_meta,
+        );
+
+        write_file(self.config.dst_dir().join("graph.html"), content).map_err(Into::into)
+    }
+
+    /// 記事一覧とアーカイブのページを、言語ごとに出力します。
+    /// 既定の言語以外の記事一覧は、言語コードのディレクトリ (`en/index.html` など) に出力します。
+    pub fn render_listings(&self, metas: &[Metadata]) -> Result<()> {
+        for locale in self.config.locales() {
+            let metas = metas
+                .iter()
+                .filter(|m| m.lang() == locale.lang && m.is_listed())
+                .collect();
+            self.render_listings_of(locale, metas)?;
+        }
+        Ok(())
+    }
+
+    /// 1 つの言語の記事一覧とアーカイブのページを出力します。
+    fn render_listings_of(&self, locale: &Locale, mut metas: Vec<&Metadata>) -> Result<()> {
+        metas.sort_unstable_by(|a, b| b.update().cmp(a.update()));
+
+        // 作成日の年月ごとに記事をまとめる
+        let mut years: BTreeMap<String, BTreeMap<String, Vec<&Metadata>>> = BTreeMap::new();
+        for m in &metas {
+            let year = format!("{:04}", m.create().year());
+            let month = format!("{:02}", m.create().month());
+            years
+                .entry(year)
+                .or_default()
+                .entry(month)
+                .or_default()
+                .push(m);
+        }
+
+        let archive_links: Vec<_> = years
+            .iter()
+            .rev()
+            .map(|(year, months)| {
+                let count = months.values().map(Vec::len).sum();
+                let months = months
+                    .iter()
+                    .rev()
+                    .map(|(m, v)| (m.as_str(), v.len()))
+                    .collect();
+                ArchiveYear {
+                    year,
+                    count,
+                    months,
+                }
+            })
+            .collect();
+
+        self.render_index_pages(locale, &metas, &archive_links)?;
+        self.render_archive_pages(locale, &years, &archive_links)?;
+
+        Ok(())
+    }
+
+    /// 各言語の記事一覧の先頭ページを示す `<link rel="alternate">` を生成します。
+    fn index_alternates_html(&self, path_to_root: &Path) -> String {
+        let pages: Vec<_> = self
+            .config
+            .locales()
+            .iter()
+            .map(|l| {
+                let url = self.config.page_url(l.dir.join("")).unwrap_or_else(|| {
+                    self.config
+                        .link_style()
+                        .href_of(l.root_from(path_to_root).join("index.html"))
+                });
+                (l.lang.as_str(), url)
+            })
+            .collect();
+        alternates_html(&pages, &self.config.default_locale().lang)
+    }
+
+    fn render_index_pages(
+        &self,
+        locale: &Locale,
+        metas: &[&Metadata],
+        archive_links: &[ArchiveYear],
+    ) -> Result<()> {
+        let page_path = |i: usize| -> PathBuf {
+            if i == 0 {
+                locale.dir.join("index.html")
+            } else {
+                locale.dir.join(format!("page/{}.html", i + 1))
+            }
+        };
+
+        let tags: Vec<_> = metas
+            .iter()
+            .flat_map(|m| m.tags())
+            .map(|t| &t[..])
+            .unique()
+            .collect();
+
+        let chunks: Vec<_> = metas.chunks(self.config.index_page_size()).collect();
+        let total = chunks.len().max(1);
+        for i in 0..total {
+            let path = page_path(i);
+            let path_to_root = path_to_root_of(&path);
+            let lang_root = locale.root_from(&path_to_root);
+
+            let css_list = self
+                .default_css_list()
+                .into_iter()
+                .chain(self.config.css_list().iter().map(|p| &p[..]));
+            let js_list = self
+                .default_js_list()
+                .into_iter()
+                .chain(self.config.js_list().iter().map(|p| &p[..]));
+
+            let chunk = chunks.get(i).copied().unwrap_or_default();
+            let contents = cards_html(chunk, &path_to_root, &lang_root, self.config.link_style());
+            let prev = (i > 0).then(|| path_to_root.join(page_path(i - 1)));
+            let next = (i + 1 < total).then(|| path_to_root.join(page_path(i + 1)));
+            let pagination = pagination_html(
+                prev.as_deref(),
+                next.as_deref(),
+                i + 1,
+                total,
+                &locale.strings,
+            );
+
+            let tags = tag_list_html(tags.iter().copied(), &lang_root, self.config.link_style());
+            let archives = archive_links_html(archive_links, &lang_root);
+
+            let url = self.config.page_url(if i == 0 {
+                locale.dir.join("")
+            } else {
+                path.clone()
+            });
+            let social_meta = self.social_meta_of_listing(self.config.site_name(), url.as_deref());
+            let alternates = if i == 0 {
+                self.index_alternates_html(&path_to_root)
+            } else {
+                String::new()
+            };
+
+            let content = index_html(
+                &path_to_root,
+                locale,
+                self.config.link_style(),
+                self.config.site_name(),
+                css_list,
+                js_list,
+                self.config.footer(),
+                &social_meta,
+                &alternates,
+                &contents,
+                &pagination,
+                &tags,
+                &archives,
+            );
+
+            let dst = self.config.dst_dir().join(&path);
+            write_file(dst, content)?;
+        }
+
+        Ok(())
+    }
+
+    fn render_archive_pages(
+        &self,
+        locale: &Locale,
+        years: &BTreeMap<String, BTreeMap<String, Vec<&Metadata>>>,
+        archive_links: &[ArchiveYear],
+    ) -> Result<()> {
+        let path_to_root = path_to_root_of(&locale.dir.join("archive/index.html"));
+        let lang_root = locale.root_from(&path_to_root);
+        let archives = archive_links_html(archive_links, &lang_root);
+
+        let render = |name: String, mut metas: Vec<&Metadata>| -> Result<()> {
+            metas.sort_unstable_by(|a, b| b.create().cmp(a.create()));
+
+            let css_list = self
+                .default_css_list()
+                .into_iter()
+                .chain(self.config.css_list().iter().map(|p| &p[..]));
+            let js_list = self
+                .default_js_list()
+                .into_iter()
+                .chain(self.config.js_list().iter().map(|p| &p[..]));
+
+            let path = locale.dir.join(format!("archive/{name}.html"));
+            let heading = format!("{}: {name}", locale.strings.archive);
+            let url = self.config.page_url(&path);
+            let social_meta = self.social_meta_of_listing(&heading, url.as_deref());
+
+            let content = archive_html(
+                &path_to_root,
+                locale,
+                self.config.link_style(),
+                self.config.site_name(),
+                &escape_html(&heading),
+                css_list,
+                js_list,
+                self.config.footer(),
+                &social_meta,
+                &cards_html(&metas, &path_to_root, &lang_root, self.config.link_style()),
+                &archives,
+            );
+
+            let dst = self.config.dst_dir().join(path);
+            write_file(dst, content).map_err(Into::into)
+        };
+
+        for (year, months) in years {
+            render(year.clone(), months.values().flatten().copied().collect())?;
+            for (month, metas) in months {
+                render(format!("{year}-{month}"), metas.clone())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Renderer, SkipReason};
+    use crate::command::build::renderer::context::Context;
+    use crate::config::{Config, FileConfig, Schedule};
+    use crate::util::PageDate;
+    use chrono_tz::Tz;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    #[test]
+    fn test_skip_reason() {
+        let tz = Tz::Asia__Tokyo;
+        let date = |s| Some(PageDate::parse(s, tz).unwrap());
+        let now = PageDate::parse("2024-06-01T10:00:00+09:00", tz).unwrap();
+        let schedule = Schedule::default();
+        let reason = |publish, expire| SkipReason::of(&schedule, now, publish, expire);
+
+        // 時刻まで含めて比較する
+        assert_eq!(reason(date("2024-06-01"), None), None);
+        assert_eq!(reason(date("2024-06-01T09:00:00+09:00"), None), None);
+        let publish = date("2024-06-01T11:00:00+09:00");
+        assert_eq!(
+            reason(publish, None),
+            Some(SkipReason::Scheduled(publish.unwrap()))
+        );
+        // オフセットの異なる日時も、同じ時点として比較する
+        let publish = date("2024-06-01T01:30:00Z");
+        assert_eq!(
+            reason(publish, None),
+            Some(SkipReason::Scheduled(publish.unwrap()))
+        );
+
+        // 公開期限の日時以降は変換しない
+        let expire = date("2024-06-01T10:00:00+09:00");
+        assert_eq!(
+            reason(None, expire),
+            Some(SkipReason::Expired(expire.unwrap()))
+        );
+        assert_eq!(reason(None, date("2024-06-02")), None);
+
+        let schedule = Schedule {
+            render_future: true,
+            render_expired: true,
+            ..Schedule::default()
+        };
+        let publish = date("2024-06-02");
+        assert_eq!(
+            SkipReason::of(&schedule, now, publish, date("2024-06-01")),
+            None
         );
+    }
+
+    /// テスト用の git リポジトリでコミットします。
+    fn commit(repo: &Path, file: &str, content: &str, date: &str) {
+        std::fs::write(repo.join(file), content).unwrap();
+        for args in [vec!["add", file], vec!["commit", "-q", "-m", file]] {
+            let status = Command::new("git")
+                .current_dir(repo)
+                .args([
+                    "-c",
+                    "user.name=zakki",
+                    "-c",
+                    "user.email=zakki@example.com",
+                ])
+                .args(args)
+                .env("GIT_AUTHOR_DATE", date)
+                .env("GIT_COMMITTER_DATE", date)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+    }
+
+    #[test]
+    fn test_fill_dates() {
+        let root =
+            std::env::temp_dir().join(format!("zakki-test-fill-dates-{}", std::process::id()));
+        let repo = root.join("repo");
+        std::fs::create_dir_all(&repo).unwrap();
+        let init = Command::new("git")
+            .current_dir(&repo)
+            .args(["init", "-q"])
+            .status();
+        if !init.is_ok_and(|s| s.success()) {
+            // git がない環境では確かめない
+            return;
+        }
+
+        let file_config: FileConfig = toml::from_str(
+            r#"
+            site_name = "test"
+            dates_from = "git"
+            timezone = "Asia/Tokyo"
+            "#,
+        )
+        .unwrap();
+        let cfg = Config::new(
+            file_config,
+            false,
+            PathBuf::from("src"),
+            PathBuf::from("build"),
+        );
+        let renderer = Renderer {
+            config: &cfg,
+            permalinks: Default::default(),
+            prepared: Default::default(),
+            skipped: Default::default(),
+        };
+        let dates = |src: &Path| {
+            let mut ctxt = Context::default();
+            renderer.fill_dates(src, &mut ctxt).unwrap();
+            let create = ctxt.create_date().unwrap().to_rfc3339();
+            let update = ctxt.last_update_date().unwrap().to_rfc3339();
+            (create, update)
+        };
+
+        // 作成日は最初のコミット、更新日は最後のコミットの日時 (サイトのタイムゾーン)
+        commit(&repo, "a.md", "1", "2024-01-01T00:00:00Z");
+        commit(&repo, "a.md", "2", "2024-02-01T12:00:00Z");
+        let (create, update) = dates(&repo.join("a.md"));
+        assert_eq!(create, "2024-01-01T09:00:00+09:00");
+        assert_eq!(update, "2024-02-01T21:00:00+09:00");
+
+        // コミットされていない変更があれば、更新日はファイルの更新日時
+        std::fs::write(repo.join("a.md"), "3").unwrap();
+        let (create, update) = dates(&repo.join("a.md"));
+        assert_eq!(create, "2024-01-01T09:00:00+09:00");
+        assert!(update.as_str() > "2024-02-01T21:00:00+09:00");
+
+        // コミットされていないファイルと git リポジトリの外のファイルは、ファイルの更新日時
+        for file in [repo.join("b.md"), root.join("c.md")] {
+            std::fs::write(&file, "").unwrap();
+            let (create, update) = dates(&file);
+            assert_eq!(create, update);
+            assert!(create.as_str() > "2024-02-01T21:00:00+09:00");
+        }
 
-        let dst = self.config.dst_dir().join("index.html");
-        write_file(dst, content).map_err(Into::into)
+        std::fs::remove_dir_all(&root).unwrap();
     }
 }
@@ -1,8 +1,11 @@
-use crate::util::BloomFilter;
+use crate::util::{BloomFilter, LinkStyle, PageDate, PathExt as _, decode_url_path};
 use anyhow::{Context as _, Result, anyhow};
+use chrono_tz::Tz;
 use paste::paste;
 use serde::Serialize;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use super::pass::{HighlightRule, Toc};
 
@@ -28,11 +31,11 @@ macro_rules! setter {
 
 #[derive(Default)]
 pub struct Context {
-    /// 記事を作成した日付 (yyyy-MM-dd)
-    create_date: Option<String>,
+    /// 記事を作成した日付
+    create_date: Option<PageDate>,
 
-    /// 記事を最後に更新したした日付 (yyyy-MM-dd)
-    last_update_date: Option<String>,
+    /// 記事を最後に更新したした日付
+    last_update_date: Option<PageDate>,
 
     /// 記事につけられたタグ
     tags: Option<Vec<String>>,
@@ -40,12 +43,39 @@ pub struct Context {
     /// 記事のタイトル
     title: Option<String>,
 
+    /// 記事を代表する画像 (OGP 用)
+    image: Option<String>,
+
+    /// 記事の概要
+    description: Option<String>,
+
     /// ルートから記事の出力先への相対パス
     build_root_to_dst: Option<PathBuf>,
 
     /// Bloom filter
     bloom_filter: Option<BloomFilter>,
 
+    /// 本文に含まれる単語の集合
+    words: Option<HashSet<String>>,
+
+    /// 本文のテキスト (検索結果の抜粋用)
+    text: Option<String>,
+
+    /// 記事が属するシリーズ
+    series: Option<String>,
+
+    /// 記事から他の記事へのリンク (ルートからの相対パス)
+    links: Vec<PathBuf>,
+
+    /// `src/` から記事のソースファイルへの相対パス
+    src_root_to_src: Option<PathBuf>,
+
+    /// 全記事の、 `src/` からのソースファイルのパスとルートからの出力先のパスの対応
+    pub permalinks: Arc<HashMap<PathBuf, PathBuf>>,
+
+    /// 記事から参照している画像などのファイル (記事のディレクトリからの相対パス)
+    assets: Vec<PathBuf>,
+
     /// コードハイライトの設定
     highlights: Option<Vec<HighlightRule>>,
 
@@ -64,19 +94,59 @@ pub struct Context {
     /// 下書きか否か
     pub is_draft: bool,
 
+    /// 日付の解釈に使うサイトのタイムゾーン
+    pub timezone: Tz,
+
+    /// リンクに書く URL の形式
+    pub link_style: LinkStyle,
+
+    /// 変更履歴のページを出力するか
+    pub history: bool,
+
+    /// 記事を公開する日時 (この日時より前は変換しない)
+    pub publish_date: Option<PageDate>,
+
+    /// 記事の公開期限 (この日時以降は変換しない)
+    pub expire_date: Option<PageDate>,
+
+    /// 出力先のファイル名 (拡張子を除く) またはパターンの `:slug`
+    pub slug: Option<String>,
+
+    /// 出力先のパスまたはパターン
+    pub permalink: Option<String>,
+
+    /// 記事へ転送するページを出力するパス
+    pub aliases: Vec<String>,
+
+    /// 記事の言語
+    pub lang: Option<String>,
+
+    /// 翻訳を同じグループにまとめるためのキー (言語コードを除いたソースファイルのパス)
+    translation_key: Option<PathBuf>,
+
     /// 暗号化するか否か
     pub to_encrypt: bool,
+
+    /// 記事一覧などに載せるか否か
+    pub is_listed: bool,
+
+    /// サイト内検索の対象から除外するか否か
+    pub exclude_from_search: bool,
 }
 
 impl Context {
-    try_get!(create_date, &String);
-    try_get!(last_update_date, &String);
+    try_get!(create_date, &PageDate);
+    try_get!(last_update_date, &PageDate);
     try_get!(tags, &Vec<String>);
     try_get!(title, &String);
+    try_get!(image, &String);
+    try_get!(description, &String);
     try_get!(build_root_to_dst, &PathBuf);
+    try_get!(src_root_to_src, &PathBuf);
     try_get!(highlights, &Vec<HighlightRule>);
     try_get!(password, &String);
     try_get!(toc, &Toc);
+    try_get!(text, &String);
 
     pub fn css_list(&self) -> &Vec<String> {
         &self.css_paths
@@ -86,23 +156,51 @@ impl Context {
         &self.js_paths
     }
 
-    setter!(create_date, String);
-    setter!(last_update_date, String);
+    setter!(create_date, PageDate);
+    setter!(last_update_date, PageDate);
     setter!(tags, Vec<String>);
     setter!(title, String);
+    setter!(image, String);
+    setter!(description, String);
     setter!(build_root_to_dst, PathBuf);
+    setter!(src_root_to_src, PathBuf);
     setter!(bloom_filter, BloomFilter);
+    setter!(words, HashSet<String>);
+    setter!(text, String);
+    setter!(series, String);
     setter!(password, String);
     setter!(highlights, Vec<HighlightRule>);
     setter!(toc, Toc);
-
-    pub fn push_js_path(&mut self, path: impl Into<String>) {
-        self.js_paths.push(path.into());
-    }
+    setter!(translation_key, PathBuf);
 
     pub fn push_css_path(&mut self, path: impl Into<String>) {
         self.css_paths.push(path.into());
     }
+
+    pub fn push_link(&mut self, path: PathBuf) {
+        self.links.push(path);
+    }
+
+    #[cfg(test)]
+    pub fn links(&self) -> &Vec<PathBuf> {
+        &self.links
+    }
+
+    /// 記事から参照しているファイルの URL を記録します。
+    /// 外部の URL と、ルートからの絶対パスは記録しません。
+    /// パーセントエンコードされた URL は、ファイルのパスに戻して記録します。
+    pub fn push_asset_url(&mut self, url: &str) {
+        let is_relative = !url.contains(':') && !url.starts_with('/') && !url.starts_with('#');
+        let path = url.split(['#', '?']).next().unwrap();
+        if is_relative && !path.is_empty() {
+            let path = decode_url_path(path);
+            self.assets.push(Path::new(&path).normalized());
+        }
+    }
+
+    pub fn assets(&self) -> &Vec<PathBuf> {
+        &self.assets
+    }
 }
 
 impl TryInto<Metadata> for Context {
@@ -116,24 +214,42 @@ impl TryInto<Metadata> for Context {
             };
         }
 
+        // 暗号化するページの概要は公開しない
+        let description = if self.to_encrypt {
+            None
+        } else {
+            self.description
+        };
+
         Ok(Metadata {
             create: try_take!(create_date),
             update: try_take!(last_update_date),
             tags: try_take!(tags),
             title: try_take!(title),
+            description,
             path: try_take!(build_root_to_dst),
             bloom_filter: try_take!(bloom_filter),
+            words: try_take!(words),
+            text: try_take!(text),
+            series: self.series,
+            lang: try_take!(lang),
+            translation_key: try_take!(translation_key),
+            links: self.links,
+            exclude_from_search: self.exclude_from_search,
+            is_draft: self.is_draft,
+            is_private: self.to_encrypt,
+            is_unlisted: !self.is_listed,
         })
     }
 }
 
-#[derive(Default, Serialize)]
+#[derive(Serialize)]
 pub struct Metadata {
-    /// 記事を作成した日付 (yyyy-MM-dd)
-    create: String,
+    /// 記事を作成した日付
+    create: PageDate,
 
-    /// 記事を最後に更新したした日付 (yyyy-MM-dd)
-    update: String,
+    /// 記事を最後に更新したした日付
+    update: PageDate,
 
     /// 記事につけられたタグ
     tags: Vec<String>,
@@ -141,16 +257,75 @@ pub struct Metadata {
     /// 記事のタイトル
     title: String,
 
+    /// 記事の概要
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+
     /// ルートから記事の出力先への相対パス
     path: PathBuf,
 
+    /// 記事が属するシリーズ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    series: Option<String>,
+
+    /// 記事の言語
+    lang: String,
+
+    /// 翻訳を同じグループにまとめるためのキー
+    #[serde(skip)]
+    translation_key: PathBuf,
+
     /// Bloom filter
     #[serde(skip)]
     bloom_filter: BloomFilter,
+
+    /// 本文に含まれる単語の集合
+    #[serde(skip)]
+    words: HashSet<String>,
+
+    /// 本文のテキスト (検索結果の抜粋用)
+    #[serde(skip)]
+    text: String,
+
+    /// 記事から他の記事へのリンク (ルートからの相対パス)
+    #[serde(skip)]
+    links: Vec<PathBuf>,
+
+    /// サイト内検索の対象から除外するか否か
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    exclude_from_search: bool,
+
+    /// 下書きか否か
+    #[serde(rename = "draft", skip_serializing_if = "std::ops::Not::not")]
+    is_draft: bool,
+
+    /// 暗号化された記事か否か
+    #[serde(rename = "crypto", skip_serializing_if = "std::ops::Not::not")]
+    is_private: bool,
+
+    /// 記事一覧などに載せない記事か否か
+    #[serde(rename = "unlisted", skip_serializing_if = "std::ops::Not::not")]
+    is_unlisted: bool,
 }
 
 impl Metadata {
-    pub fn update(&self) -> &String {
+    pub fn create(&self) -> &PageDate {
+        &self.create
+    }
+
+    pub fn title(&self) -> &String {
+        &self.title
+    }
+
+    pub fn tags(&self) -> &Vec<String> {
+        &self.tags
+    }
+
+    pub fn description(&self) -> Option<&String> {
+        self.description.as_ref()
+    }
+
+    pub fn update(&self) -> &PageDate {
         &self.update
     }
 
@@ -161,4 +336,46 @@ impl Metadata {
     pub fn path(&self) -> &PathBuf {
         &self.path
     }
+
+    /// 暗号化された記事かどうか
+    pub fn is_private(&self) -> bool {
+        self.is_private
+    }
+
+    /// 記事一覧、タグ、アーカイブ、ナビゲーション、フィード、サイトマップに載せるか
+    pub fn is_listed(&self) -> bool {
+        !self.is_unlisted
+    }
+
+    pub fn links(&self) -> &Vec<PathBuf> {
+        &self.links
+    }
+
+    pub fn series(&self) -> Option<&String> {
+        self.series.as_ref()
+    }
+
+    pub fn lang(&self) -> &str {
+        &self.lang
+    }
+
+    pub fn translation_key(&self) -> &PathBuf {
+        &self.translation_key
+    }
+
+    pub fn words(&self) -> &HashSet<String> {
+        &self.words
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn exclude_from_search(&self) -> bool {
+        self.exclude_from_search
+    }
+
+    pub fn is_draft(&self) -> bool {
+        self.is_draft
+    }
 }
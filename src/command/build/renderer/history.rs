@@ -0,0 +1,94 @@
+use super::pass::{Toc, TocBuilder};
+use crate::util::{LinkStyle, PageDate, Revision, escape_html};
+use chrono_tz::Tz;
+use std::path::{Path, PathBuf};
+
+/// 記事の変更履歴のページの出力先 (`foo.html` に対して `foo.history.html`) を返します。
+pub fn history_path_of(page: &Path) -> PathBuf {
+    page.with_extension("history.html")
+}
+
+/// 記事から変更履歴のページへのリンクを返します。
+/// `label` はリンクの文言です。
+pub fn history_link_html(page: &Path, label: &str) -> String {
+    let href = history_path_of(page);
+    let href = href.file_name().unwrap().to_str().unwrap();
+    let label = escape_html(label);
+    format!(r#"<p class="history-link"><a href="{href}">{label}</a></p>"#)
+}
+
+/// unified diff を、追加・削除された行を色分けした HTML に変換します。
+/// ファイル名などのヘッダは省き、 `@@` で始まる行以降を出力します。
+fn diff_html(diff: &str) -> String {
+    let lines: Vec<_> = diff
+        .lines()
+        .skip_while(|l| !l.starts_with("@@"))
+        .map(|l| {
+            let class = match l.chars().next() {
+                Some('+') => "diff-add",
+                Some('-') => "diff-del",
+                Some('@') => "diff-hunk",
+                _ => "diff-ctx",
+            };
+            format!(r#"<span class="{class}">{}</span>"#, escape_html(l))
+        })
+        .collect();
+    format!(
+        r#"<pre class="diff"><code>{}</code></pre>"#,
+        lines.join("\n")
+    )
+}
+
+/// 変更履歴のページの本文と目次を生成します。
+/// `heading` は見出しの文言 (記事のタイトルの前に付ける) 、 `page` は記事の出力先、
+/// `revisions` は新しいコミットから順に並んだ変更履歴です。
+pub fn history_body(
+    heading: &str,
+    title: &str,
+    page: &Path,
+    revisions: &[Revision],
+    tz: Tz,
+    link_style: &LinkStyle,
+) -> (String, Toc) {
+    let heading = escape_html(heading);
+    let title = escape_html(title);
+    let href = link_style.href_of(page.file_name().unwrap());
+
+    let mut body = format!(r#"<h1>{heading}: {title}</h1><p><a href="{href}">{title}</a></p>"#);
+    let mut toc = TocBuilder::new();
+    for rev in revisions {
+        let short = &rev.hash[..rev.hash.len().min(7)];
+        let id = format!("rev-{short}");
+        let date = PageDate::from_datetime(rev.date, tz);
+        let heading = format!("{date} {}", escape_html(&rev.subject));
+
+        body += &format!(r#"<h2 id="{id}">{heading}</h2>"#);
+        body += &format!(r#"<p class="history-meta"><code>{short}</code></p>"#);
+        body += &diff_html(&rev.diff);
+        toc.add_item(heading, id, 1);
+    }
+
+    (body, toc.build())
+}
+
+#[cfg(test)]
+mod test {
+    use super::diff_html;
+
+    #[test]
+    fn test_diff_html() {
+        let diff = "diff --git a/foo.md b/foo.md\n--- a/foo.md\n+++ b/foo.md\n@@ -1,2 +1,2 @@\n 本文\n-<b>古い</b>\n+新しい";
+        assert_eq!(
+            diff_html(diff),
+            concat!(
+                r#"<pre class="diff"><code><span class="diff-hunk">@@ -1,2 +1,2 @@</span>"#,
+                "\n",
+                r#"<span class="diff-ctx"> 本文</span>"#,
+                "\n",
+                r#"<span class="diff-del">-&lt;b&gt;古い&lt;/b&gt;</span>"#,
+                "\n",
+                r#"<span class="diff-add">+新しい</span></code></pre>"#,
+            )
+        );
+    }
+}
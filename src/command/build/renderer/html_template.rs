@@ -1,40 +1,169 @@
+use crate::config::{Locale, UiStrings};
 use crate::include_asset;
-use std::path::{Path, PathBuf};
+use crate::util::{LinkStyle, PageDate, escape_html};
+use anyhow::{Result, bail};
+use itertools::Itertools;
+use std::path::Path;
 
+use super::context::Metadata;
 use super::pass::Toc;
 
-fn adjust_path_origin(path: &str, path_to_root: &Path) -> String {
-    if path.starts_with("http://") || path.starts_with("https://") || path.starts_with("/") {
+/// 他の記事へのナビゲーションを埋め込む位置を示すマーカー
+/// 全記事の描画後に、ナビゲーションの HTML で置き換えられます。
+pub const NAVIGATION_MARKER: &str = "<!-- zakki:navigation -->";
+
+/// 他の言語の記事へのリンクを埋め込む位置を示すマーカー
+/// 全記事の描画後に、言語の切り替えの HTML で置き換えられます。
+pub const TRANSLATIONS_MARKER: &str = "<!-- zakki:translations -->";
+
+/// 他の言語の記事を示す `<link rel="alternate">` を埋め込む位置を示すマーカー
+pub const ALTERNATES_MARKER: &str = "<!-- zakki:alternates -->";
+
+/// 設定やヘッダで指定された CSS, JS のパスを、ページから参照できる URL に変換します。
+/// ルートからの絶対パス (`/` で始まるパス) には、サイトのベースパスを付けます。
+fn adjust_path_origin(path: &str, path_to_root: &Path, link_style: &LinkStyle) -> String {
+    if path.starts_with("http://") || path.starts_with("https://") || path.starts_with("//") {
         return path.to_string();
     }
+    if path.starts_with('/') {
+        return link_style.with_base_path(path);
+    }
     path_to_root.join(path).to_str().unwrap().to_string()
 }
 
-fn header(path_to_root: &Path, site_name: &str) -> String {
+/// ページ上部のヘッダを生成します。
+/// サイト名のリンクは、ページの言語の記事一覧を指します。
+fn header(path_to_root: &Path, site_name: &str, locale: &Locale, link_style: &LinkStyle) -> String {
+    let home = locale.root_from(path_to_root).join("index.html");
     format!(
         include_asset!("header.html"),
-        path_to_root = path_to_root.to_str().unwrap(),
+        home = link_style.href_of(home),
         site_name = site_name,
+        search_placeholder = escape_html(&locale.strings.search_placeholder),
     )
 }
 
+/// OGP, Twitter Card, JSON-LD の生成に使うページの情報
+pub struct SocialMeta<'a> {
+    pub site_name: &'a str,
+    pub title: &'a str,
+    pub description: &'a str,
+    /// ページの公開 URL
+    pub url: Option<&'a str>,
+    /// 代表画像の URL
+    pub image: Option<&'a str>,
+    /// 記事の作成日と最終更新日 (記事ページのみ)
+    pub dates: Option<(&'a PageDate, &'a PageDate)>,
+    /// 検索エンジンにインデックスさせないか (下書き)
+    pub noindex: bool,
+}
+
+impl SocialMeta<'_> {
+    fn to_html(&self) -> String {
+        let mut tags = Vec::new();
+        let mut meta = |attr: &str, key: &str, value: &str| {
+            tags.push(format!(
+                r#"<meta {attr}="{key}" content="{}" />"#,
+                escape_html(value)
+            ));
+        };
+
+        let og_type = if self.dates.is_some() {
+            "article"
+        } else {
+            "website"
+        };
+        if self.noindex {
+            meta("name", "robots", "noindex");
+        }
+        meta("name", "description", self.description);
+        meta("property", "og:site_name", self.site_name);
+        meta("property", "og:title", self.title);
+        meta("property", "og:description", self.description);
+        meta("property", "og:type", og_type);
+        if let Some(url) = self.url {
+            meta("property", "og:url", url);
+        }
+        if let Some(image) = self.image {
+            meta("property", "og:image", image);
+        }
+        if let Some((create, update)) = self.dates {
+            meta("property", "article:published_time", &create.to_rfc3339());
+            meta("property", "article:modified_time", &update.to_rfc3339());
+        }
+
+        let card = if self.image.is_some() {
+            "summary_large_image"
+        } else {
+            "summary"
+        };
+        meta("name", "twitter:card", card);
+        meta("name", "twitter:title", self.title);
+        meta("name", "twitter:description", self.description);
+        if let Some(image) = self.image {
+            meta("name", "twitter:image", image);
+        }
+
+        if let Some(url) = self.url {
+            tags.push(format!(
+                r#"<link rel="canonical" href="{}" />"#,
+                escape_html(url)
+            ));
+        }
+
+        if let Some((create, update)) = self.dates {
+            tags.push(self.json_ld(&create.to_rfc3339(), &update.to_rfc3339()));
+        }
+
+        tags.join("\n  ")
+    }
+
+    /// 記事の JSON-LD を生成します。
+    fn json_ld(&self, create: &str, update: &str) -> String {
+        let mut article = serde_json::json!({
+            "@context": "https://schema.org",
+            "@type": "Article",
+            "headline": self.title,
+            "description": self.description,
+            "datePublished": create,
+            "dateModified": update,
+            "publisher": { "@type": "Organization", "name": self.site_name },
+        });
+        if let Some(url) = self.url {
+            article["url"] = url.into();
+            article["mainEntityOfPage"] = url.into();
+        }
+        if let Some(image) = self.image {
+            article["image"] = image.into();
+        }
+
+        // script 要素を途中で閉じさせないよう、 "</" をエスケープする
+        let json = article.to_string().replace("</", "<\\/");
+        format!(r#"<script type="application/ld+json">{json}</script>"#)
+    }
+}
+
+/// `alternates` は他の言語のページを示す `<link rel="alternate">` です。
 fn head<'a>(
     path_to_root: &Path,
+    link_style: &LinkStyle,
     css_list: impl Iterator<Item = &'a str>,
     js_list: impl Iterator<Item = &'a str>,
     title: &str,
+    social_meta: &SocialMeta,
+    alternates: &str,
 ) -> String {
     let css_list = css_list.map(|p| {
         format!(
             r#"<link rel="stylesheet" href="{}" />"#,
-            adjust_path_origin(p, path_to_root)
+            adjust_path_origin(p, path_to_root, link_style)
         )
     });
 
     let js_list = js_list.map(|p| {
         format!(
             r#"<script defer type="text/javascript" src="{}"></script>"#,
-            adjust_path_origin(p, path_to_root)
+            adjust_path_origin(p, path_to_root, link_style)
         )
     });
 
@@ -44,92 +173,531 @@ fn head<'a>(
         css_list = css_list.collect::<String>(),
         js_list = js_list.collect::<String>(),
         title = title,
+        social_meta = social_meta.to_html(),
+        alternates = alternates,
     )
 }
 
-fn tag_elems(tags: &[String], dst_root_dir: &Path) -> String {
+/// 他の言語のページを示す `<link rel="alternate">` を生成します。
+/// `pages` は (言語コード, URL) の一覧で、ページ自身も含みます。
+/// 既定の言語のページは `x-default` にもします。
+pub fn alternates_html(pages: &[(&str, String)], default_lang: &str) -> String {
+    if pages.len() <= 1 {
+        return String::new();
+    }
+    let link = |lang: &str, url: &str| {
+        format!(
+            r#"<link rel="alternate" hreflang="{lang}" href="{}" />"#,
+            escape_html(url)
+        )
+    };
+    pages
+        .iter()
+        .map(|(lang, url)| link(lang, url))
+        .chain(
+            pages
+                .iter()
+                .find(|(lang, _)| *lang == default_lang)
+                .map(|(_, url)| link("x-default", url)),
+        )
+        .join("\n  ")
+}
+
+/// 他の言語の記事へのリンク (言語の切り替え) を生成します。
+/// `pages` は (言語, ページからの相対 URL) の一覧で、ページ自身は含みません。
+pub fn translations_html(pages: &[(&Locale, String)], strings: &UiStrings) -> String {
+    if pages.is_empty() {
+        return String::new();
+    }
+    let links = pages
+        .iter()
+        .map(|(locale, href)| {
+            format!(
+                r#"<a rel="alternate" hreflang="{lang}" lang="{lang}" href="{href}">{name}</a>"#,
+                lang = locale.lang,
+                name = escape_html(&locale.strings.language_name),
+            )
+        })
+        .join(" ");
+    format!(
+        r#"<nav id="translations"><span>{}:</span> {links}</nav>"#,
+        escape_html(&strings.translations)
+    )
+}
+
+/// タグで絞り込んだ記事一覧へのリンクを生成します。
+/// `dst_root_dir` はページの言語の記事一覧のディレクトリへの相対パスです。
+fn tag_elem(tag: &str, dst_root_dir: &Path, link_style: &LinkStyle) -> String {
+    let path = link_style.href_of(dst_root_dir.join("index.html"));
+    format!(r#"<a class="tag" href="{path}?tag={tag}">{tag}</a>"#)
+}
+
+fn tag_elems(tags: &[String], dst_root_dir: &Path, link_style: &LinkStyle) -> String {
     let nsbp = "\u{00a0}";
     tags.iter()
-        .map(|n| {
-            let path = dst_root_dir.join("index.html");
-            let path = path.to_str().unwrap();
-            format!(r#"<a class="tag" href="{path}?tag={n}">{n}</a>"#)
-        })
+        .map(|n| tag_elem(n, dst_root_dir, link_style))
         .fold(String::new(), |acc, e| format!("{acc}{nsbp}{e}"))
 }
 
+/// 記事一覧のカードを生成します。
+/// `script.js` の `createCard` と同じ構造の HTML を出力します。
+/// `lang_root` は記事一覧の言語のディレクトリへの相対パスです。
+pub fn cards_html(
+    metas: &[&Metadata],
+    path_to_root: &Path,
+    lang_root: &Path,
+    link_style: &LinkStyle,
+) -> String {
+    metas
+        .iter()
+        .map(|m| {
+            let crypto = if m.is_private() { " crypto" } else { "" };
+            let href = link_style.href_of(path_to_root.join(m.path()));
+            let description = m
+                .description()
+                .map(|d| format!(r#"<div class="card-description">{}</div>"#, escape_html(d)))
+                .unwrap_or_default();
+            let tags = m
+                .tags()
+                .iter()
+                .map(|t| tag_elem(t, lang_root, link_style))
+                .collect::<String>();
+
+            format!(
+                concat!(
+                    r#"<a class="card{crypto}" href="{href}">"#,
+                    r#"<div class="card-header">{title}</div>{description}"#,
+                    r#"<div class="card-meta"><div class="card-date">{date}</div>"#,
+                    r#"<div class="card-tags">{tags}</div></div></a>"#,
+                ),
+                crypto = crypto,
+                href = href,
+                title = m.title(),
+                description = description,
+                date = m.update(),
+                tags = tags,
+            )
+        })
+        .collect()
+}
+
+/// 記事一覧のページ送りを生成します。
+/// * `prev`, `next` - 前後のページへのリンク
+pub fn pagination_html(
+    prev: Option<&Path>,
+    next: Option<&Path>,
+    current: usize,
+    total: usize,
+    strings: &UiStrings,
+) -> String {
+    if total <= 1 {
+        return String::new();
+    }
+
+    let link = |path: Option<&Path>, rel: &str, label: &str| match path {
+        Some(p) => format!(
+            r#"<a rel="{rel}" href="{}">{label}</a>"#,
+            p.to_str().unwrap()
+        ),
+        None => format!(r#"<span class="disabled">{label}</span>"#),
+    };
+
+    format!(
+        r#"<nav class="pagination">{}<span>{current} / {total}</span>{}</nav>"#,
+        link(
+            prev,
+            "prev",
+            &format!("&laquo; {}", escape_html(&strings.newer))
+        ),
+        link(
+            next,
+            "next",
+            &format!("{} &raquo;", escape_html(&strings.older))
+        ),
+    )
+}
+
+/// タグの一覧を生成します。
+pub fn tag_list_html<'a>(
+    tags: impl Iterator<Item = &'a str>,
+    path_to_root: &Path,
+    link_style: &LinkStyle,
+) -> String {
+    tags.map(|t| tag_elem(t, path_to_root, link_style))
+        .join(" ")
+}
+
+/// アーカイブの 1 年分の記事数
+pub struct ArchiveYear<'a> {
+    pub year: &'a str,
+    pub count: usize,
+    /// (月, 記事数) の一覧
+    pub months: Vec<(&'a str, usize)>,
+}
+
+/// アーカイブページへのリンク一覧を生成します。
+/// `lang_root` は記事一覧の言語のディレクトリへの相対パスです。
+pub fn archive_links_html(years: &[ArchiveYear], lang_root: &Path) -> String {
+    years
+        .iter()
+        .map(|ArchiveYear { year, count, months }| {
+            let href = lang_root.join(format!("archive/{year}.html"));
+            let months = months
+                .iter()
+                .map(|(month, count)| {
+                    let href = lang_root.join(format!("archive/{year}-{month}.html"));
+                    format!(
+                        r#"<a href="{}">{month}</a>&nbsp;({count})"#,
+                        href.to_str().unwrap()
+                    )
+                })
+                .join(" ");
+            format!(
+                r#"<div class="archive-year"><a href="{}">{year}</a>&nbsp;({count}): {months}</div>"#,
+                href.to_str().unwrap()
+            )
+        })
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn index_html<'a>(
+    path_to_root: &Path,
+    locale: &Locale,
+    link_style: &LinkStyle,
     site_name: &str,
     css_list: impl Iterator<Item = &'a str>,
     js_list: impl Iterator<Item = &'a str>,
     footer: &str,
+    social_meta: &SocialMeta,
+    alternates: &str,
+    contents: &str,
+    pagination: &str,
+    tags: &str,
+    archives: &str,
 ) -> String {
-    let path_to_root = &PathBuf::from(".");
-    let head = head(path_to_root, css_list, js_list, site_name);
-    let header = header(path_to_root, site_name);
+    let head = head(
+        path_to_root,
+        link_style,
+        css_list,
+        js_list,
+        site_name,
+        social_meta,
+        alternates,
+    );
+    let header = header(path_to_root, site_name, locale, link_style);
     format!(
         include_asset!("index.html"),
+        lang = locale.lang,
+        head = head,
+        header = header,
+        contents = contents,
+        pagination = pagination,
+        tags = tags,
+        archives = archives,
+        footer = footer,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn archive_html<'a>(
+    path_to_root: &Path,
+    locale: &Locale,
+    link_style: &LinkStyle,
+    site_name: &str,
+    heading: &str,
+    css_list: impl Iterator<Item = &'a str>,
+    js_list: impl Iterator<Item = &'a str>,
+    footer: &str,
+    social_meta: &SocialMeta,
+    contents: &str,
+    archives: &str,
+) -> String {
+    let head = head(
+        path_to_root,
+        link_style,
+        css_list,
+        js_list,
+        heading,
+        social_meta,
+        "",
+    );
+    let header = header(path_to_root, site_name, locale, link_style);
+    format!(
+        include_asset!("archive.html"),
+        lang = locale.lang,
+        head = head,
+        header = header,
+        heading = heading,
+        contents = contents,
+        archives = archives,
+        footer = footer,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn graph_html<'a>(
+    locale: &Locale,
+    link_style: &LinkStyle,
+    site_name: &str,
+    css_list: impl Iterator<Item = &'a str>,
+    js_list: impl Iterator<Item = &'a str>,
+    footer: &str,
+    social_meta: &SocialMeta,
+) -> String {
+    let path_to_root = Path::new(".");
+    let heading = &locale.strings.link_graph;
+    let head = head(
+        path_to_root,
+        link_style,
+        css_list,
+        js_list,
+        heading,
+        social_meta,
+        "",
+    );
+    let header = header(path_to_root, site_name, locale, link_style);
+    format!(
+        include_asset!("graph.html"),
+        lang = locale.lang,
         head = head,
         header = header,
+        heading = escape_html(heading),
         footer = footer,
     )
 }
 
+/// 記事へ転送するページを生成します。
+/// `url` は転送先の URL 、 `lang` は記事の言語です。
+pub fn redirect_html(title: &str, url: &str, lang: &str) -> String {
+    format!(
+        include_asset!("redirect.html"),
+        lang = lang,
+        title = escape_html(title),
+        url = escape_html(url),
+    )
+}
+
+/// 下書きであることを示すバナーを返します。
+fn draft_banner(is_draft: bool, strings: &UiStrings) -> String {
+    if is_draft {
+        format!(
+            r#"<div id="draft-banner">{}</div>"#,
+            escape_html(&strings.draft)
+        )
+    } else {
+        String::new()
+    }
+}
+
+/// 実行時に読み込んだテンプレートの `{name}` を値で置き換えます。
+/// 組み込みのテンプレートと同様に、 `{{` と `}}` はそれぞれ `{` と `}` になります。
+fn fill_template(template: &str, values: &[(&str, &str)]) -> Result<String> {
+    let mut html = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(i) = rest.find(['{', '}']) {
+        html.push_str(&rest[..i]);
+        let brace = &rest[i..i + 1];
+        rest = &rest[i + 1..];
+        if let Some(r) = rest.strip_prefix(brace) {
+            html.push_str(brace);
+            rest = r;
+            continue;
+        }
+        if brace == "}" {
+            bail!("Unmatched `}}` in template.");
+        }
+
+        let Some((name, r)) = rest.split_once('}') else {
+            bail!("Unmatched `{{` in template.");
+        };
+        let Some((_, value)) = values.iter().find(|(k, _)| *k == name) else {
+            let names = values.iter().map(|(k, _)| *k).join(", ");
+            bail!("Unknown placeholder `{{{name}}}` in template (available: {names}).");
+        };
+        html.push_str(value);
+        rest = r;
+    }
+    html.push_str(rest);
+    Ok(html)
+}
+
+/// 記事のページの HTML を生成します。
+/// `template` を指定した場合は、組み込みの page.html の代わりに使います。
 #[allow(clippy::too_many_arguments)]
 pub fn page_html<'a>(
     path_to_root: &Path,
+    locale: &Locale,
+    link_style: &LinkStyle,
     site_name: &str,
     title: &str,
-    create_date: &str,
-    last_update_date: &str,
+    create_date: &PageDate,
+    last_update_date: &PageDate,
     css_list: impl Iterator<Item = &'a str>,
     js_list: impl Iterator<Item = &'a str>,
     tags: &[String],
     body: &str,
     footer: &str,
     toc: &Toc,
-) -> String {
-    let head = head(path_to_root, css_list, js_list, title);
-    let header = header(path_to_root, site_name);
-    let tag_elems = tag_elems(tags, path_to_root);
+    social_meta: &SocialMeta,
+    is_draft: bool,
+    template: Option<&str>,
+) -> Result<String> {
+    let head = head(
+        path_to_root,
+        link_style,
+        css_list,
+        js_list,
+        title,
+        social_meta,
+        ALTERNATES_MARKER,
+    );
+    let header = header(path_to_root, site_name, locale, link_style);
+    let tag_elems = tag_elems(tags, &locale.root_from(path_to_root), link_style);
+    let strings = &locale.strings;
 
-    format!(
+    if let Some(template) = template {
+        return fill_template(
+            template,
+            &[
+                ("lang", &locale.lang),
+                ("head", &head),
+                ("header", &header),
+                ("draft_banner", &draft_banner(is_draft, strings)),
+                ("translations", TRANSLATIONS_MARKER),
+                ("tag_elems", &tag_elems),
+                ("create_date", &create_date.to_string()),
+                ("last_update_date", &last_update_date.to_string()),
+                ("body", body),
+                ("footer_text", footer),
+                ("toc", &toc.to_html()),
+                ("navigation", NAVIGATION_MARKER),
+            ],
+        );
+    }
+
+    Ok(format!(
         include_asset!("page.html"),
+        lang = locale.lang,
         head = head,
         header = header,
+        draft_banner = draft_banner(is_draft, strings),
+        translations = TRANSLATIONS_MARKER,
         tag_elems = tag_elems,
+        created = escape_html(&strings.created),
+        updated = escape_html(&strings.updated),
         create_date = create_date,
         last_update_date = last_update_date,
         body = body,
         footer_text = footer,
         toc = toc.to_html(),
-    )
+        navigation = NAVIGATION_MARKER,
+    ))
 }
+
 #[allow(clippy::too_many_arguments)]
 pub fn crypto_html<'a>(
     path_to_root: &Path,
+    locale: &Locale,
+    link_style: &LinkStyle,
     site_name: &str,
     title: &str,
-    create_date: &str,
-    last_update_date: &str,
+    create_date: &PageDate,
+    last_update_date: &PageDate,
     css_list: impl Iterator<Item = &'a str>,
     js_list: impl Iterator<Item = &'a str>,
     tags: &[String],
     encoded_body: &str,
     footer: &str,
+    social_meta: &SocialMeta,
+    is_draft: bool,
 ) -> String {
-    let head = head(path_to_root, css_list, js_list, title);
-    let header = header(path_to_root, site_name);
-    let tag_elems = tag_elems(tags, path_to_root);
+    let head = head(
+        path_to_root,
+        link_style,
+        css_list,
+        js_list,
+        title,
+        social_meta,
+        ALTERNATES_MARKER,
+    );
+    let header = header(path_to_root, site_name, locale, link_style);
+    let tag_elems = tag_elems(tags, &locale.root_from(path_to_root), link_style);
+    let strings = &locale.strings;
 
     format!(
         include_asset!("crypto.html"),
+        lang = locale.lang,
         head = head,
+        created = escape_html(&strings.created),
+        updated = escape_html(&strings.updated),
         create_date = create_date,
         last_update_date = last_update_date,
         tag_elems = tag_elems,
         header = header,
+        draft_banner = draft_banner(is_draft, strings),
+        translations = TRANSLATIONS_MARKER,
+        protected = escape_html(&strings.protected),
+        password_placeholder = escape_html(&strings.password_placeholder),
+        decrypt = escape_html(&strings.decrypt),
         encoded = encoded_body,
         footer_text = footer,
+        navigation = NAVIGATION_MARKER,
     )
 }
+
+#[cfg(test)]
+mod test {
+    use super::{alternates_html, fill_template, translations_html};
+    use crate::config::Locale;
+
+    #[test]
+    fn test_fill_template() {
+        let values = [("title", "タイトル"), ("body", "<p>{body}</p>")];
+        assert_eq!(
+            fill_template(
+                "<h1>{title}</h1>{body}<style>a {{ color: red; }}</style>",
+                &values
+            )
+            .unwrap(),
+            "<h1>タイトル</h1><p>{body}</p><style>a { color: red; }</style>"
+        );
+
+        assert!(fill_template("{unknown}", &values).is_err());
+        assert!(fill_template("{title", &values).is_err());
+        assert!(fill_template("title}", &values).is_err());
+    }
+
+    #[test]
+    fn test_alternates_html() {
+        let pages = [
+            ("ja", "https://example.com/foo.html".to_owned()),
+            ("en", "https://example.com/foo.en.html?a&b".to_owned()),
+        ];
+        assert_eq!(
+            alternates_html(&pages, "ja"),
+            [
+                r#"<link rel="alternate" hreflang="ja" href="https://example.com/foo.html" />"#,
+                r#"<link rel="alternate" hreflang="en" href="https://example.com/foo.en.html?a&amp;b" />"#,
+                r#"<link rel="alternate" hreflang="x-default" href="https://example.com/foo.html" />"#,
+            ]
+            .join("\n  ")
+        );
+        // 既定の言語の記事がなければ x-default は出力しない
+        assert!(!alternates_html(&pages[1..], "ja").contains("x-default"));
+        assert!(!alternates_html(&pages, "fr").contains("x-default"));
+        // 翻訳のない記事には出力しない
+        assert_eq!(alternates_html(&pages[..1], "ja"), "");
+    }
+
+    #[test]
+    fn test_translations_html() {
+        let ja = Locale::new("ja", true, None).unwrap();
+        let en = Locale::new("en", false, None).unwrap();
+        assert_eq!(
+            translations_html(&[(&en, "foo.en.html".to_owned())], &ja.strings),
+            r#"<nav id="translations"><span>他の言語:</span> <a rel="alternate" hreflang="en" lang="en" href="foo.en.html">English</a></nav>"#
+        );
+        assert_eq!(translations_html(&[], &ja.strings), "");
+    }
+}
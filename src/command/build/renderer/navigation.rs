@@ -0,0 +1,249 @@
+use super::context::Metadata;
+use crate::config::UiStrings;
+use crate::util::{LinkStyle, escape_html};
+use itertools::Itertools;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// 関連記事として表示する記事の最大数
+const MAX_RELATED: usize = 5;
+
+/// 本文の類似度だけで関連記事とみなす閾値
+const MIN_SIMILARITY: f64 = 0.1;
+
+/// 記事から他の記事へのナビゲーション
+pub struct Navigation<'a> {
+    /// 1 つ前 (古い方) の記事
+    prev: Option<&'a Metadata>,
+    /// 1 つ後 (新しい方) の記事
+    next: Option<&'a Metadata>,
+    /// 前後の記事を同じシリーズから選んだ場合のシリーズ名
+    series: Option<&'a str>,
+    /// 関連記事 (関連度の高い順)
+    related: Vec<&'a Metadata>,
+    /// この記事にリンクしている記事
+    backlinks: Vec<&'a Metadata>,
+}
+
+impl Navigation<'_> {
+    pub fn to_html(
+        &self,
+        path_to_root: &Path,
+        strings: &UiStrings,
+        link_style: &LinkStyle,
+    ) -> String {
+        let link = |m: &Metadata, rel: &str, prefix: &str, suffix: &str| {
+            let href = link_style.href_of(path_to_root.join(m.path()));
+            format!(
+                r#"<a rel="{rel}" href="{href}">{prefix}{}{suffix}</a>"#,
+                escape_html(m.title()),
+            )
+        };
+
+        let mut html = String::new();
+
+        if self.prev.is_some() || self.next.is_some() {
+            html += r#"<div class="prev-next">"#;
+            if let Some(series) = self.series {
+                html += &format!(r#"<div class="series">{}</div>"#, escape_html(series));
+            }
+            html += &self
+                .prev
+                .map(|m| link(m, "prev", "&laquo;&nbsp;", ""))
+                .unwrap_or_else(|| "<span></span>".to_owned());
+            html += &self
+                .next
+                .map(|m| link(m, "next", "", "&nbsp;&raquo;"))
+                .unwrap_or_else(|| "<span></span>".to_owned());
+            html += "</div>";
+        }
+
+        if !self.related.is_empty() {
+            html += &format!(
+                r#"<section id="related-articles"><h2>{}</h2><ul>"#,
+                escape_html(&strings.related)
+            );
+            self.related.iter().for_each(|m| {
+                html += &format!("<li>{}</li>", link(m, "related", "", ""));
+            });
+            html += "</ul></section>";
+        }
+
+        if !self.backlinks.is_empty() {
+            html += &format!(
+                r#"<section id="backlinks"><h2>{}</h2><ul>"#,
+                escape_html(&strings.backlinks)
+            );
+            self.backlinks.iter().for_each(|m| {
+                html += &format!("<li>{}</li>", link(m, "backlink", "", ""));
+            });
+            html += "</ul></section>";
+        }
+
+        if html.is_empty() {
+            return html;
+        }
+        format!(r#"<nav id="page-navigation">{html}</nav>"#)
+    }
+}
+
+/// 2 つの単語集合の Jaccard 係数を返します。
+fn similarity(a: &HashSet<&str>, b: &HashSet<&str>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// 関連度の計算に使う単語の集合を返します。
+/// 助詞などのノイズを避けるため、 1 文字の単語は無視します。
+fn content_words(meta: &Metadata) -> HashSet<&str> {
+    meta.words()
+        .iter()
+        .filter(|w| w.chars().nth(1).is_some())
+        .map(|w| &w[..])
+        .collect()
+}
+
+/// 記事間のリンクを (リンク元, リンク先) の添字の組で返します。
+/// 暗号化された記事の内容を漏らさないよう、暗号化された記事からのリンクは含めません。
+fn edges(metas: &[&Metadata]) -> Vec<(usize, usize)> {
+    let index: HashMap<_, _> = metas
+        .iter()
+        .enumerate()
+        .map(|(i, m)| (m.path(), i))
+        .collect();
+
+    metas
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| !m.is_private())
+        .flat_map(|(i, m)| {
+            m.links()
+                .iter()
+                .filter_map(|l| index.get(l).copied())
+                .filter(move |&j| j != i)
+                .map(move |j| (i, j))
+        })
+        .unique()
+        .collect()
+}
+
+/// 各記事にリンクしている記事の添字を返します。
+fn backlinks(metas: &[&Metadata]) -> Vec<Vec<usize>> {
+    let mut backlinks = vec![Vec::new(); metas.len()];
+    edges(metas)
+        .into_iter()
+        .for_each(|(from, to)| backlinks[to].push(from));
+    backlinks
+        .iter_mut()
+        .for_each(|b| b.sort_by_key(|&i| metas[i].title()));
+    backlinks
+}
+
+#[derive(Serialize)]
+struct GraphNode<'a> {
+    path: &'a PathBuf,
+    title: &'a str,
+    tags: &'a [String],
+}
+
+#[derive(Serialize)]
+struct GraphLink<'a> {
+    source: &'a PathBuf,
+    target: &'a PathBuf,
+}
+
+/// サイト全体のリンクグラフ
+/// 暗号化された記事は含めません。
+#[derive(Serialize)]
+pub struct LinkGraph<'a> {
+    nodes: Vec<GraphNode<'a>>,
+    links: Vec<GraphLink<'a>>,
+}
+
+impl<'a> LinkGraph<'a> {
+    pub fn new(metas: &[&'a Metadata]) -> Self {
+        let nodes = metas
+            .iter()
+            .filter(|m| !m.is_private())
+            .map(|m| GraphNode {
+                path: m.path(),
+                title: m.title(),
+                tags: m.tags(),
+            })
+            .collect();
+
+        let links = edges(metas)
+            .into_iter()
+            .filter(|&(_, to)| !metas[to].is_private())
+            .map(|(from, to)| GraphLink {
+                source: metas[from].path(),
+                target: metas[to].path(),
+            })
+            .collect();
+
+        Self { nodes, links }
+    }
+}
+
+/// 全記事のナビゲーションを計算します。
+/// 返り値の i 番目の要素は `metas[i]` のナビゲーションです。
+pub fn navigations<'a>(metas: &[&'a Metadata]) -> Vec<Navigation<'a>> {
+    // 作成日順 (同日の場合はパス順) に並べる
+    let mut by_date: Vec<_> = metas.to_vec();
+    by_date.sort_by(|a, b| (a.create(), a.path()).cmp(&(b.create(), b.path())));
+
+    let words: Vec<_> = metas.iter().map(|m| content_words(m)).collect();
+    let backlinks = backlinks(metas);
+
+    // 記事 i から見た記事 j の関連度 (関連がなければ None)
+    let relevance = |i: usize, j: usize| -> Option<f64> {
+        let (a, b) = (metas[i], metas[j]);
+        let shared_tags = a.tags().iter().filter(|t| b.tags().contains(t)).count();
+        let similarity = similarity(&words[i], &words[j]);
+        if shared_tags == 0 && similarity < MIN_SIMILARITY {
+            return None;
+        }
+        Some(shared_tags as f64 + similarity)
+    };
+
+    (0..metas.len())
+        .into_par_iter()
+        .map(|i| {
+            let m = metas[i];
+
+            // シリーズに属する場合は、同じシリーズの記事の中で前後を決める
+            // 前後の記事と関連記事は、同じ言語の記事から選ぶ
+            let series = m.series();
+            let siblings: Vec<_> = by_date
+                .iter()
+                .filter(|o| o.lang() == m.lang())
+                .filter(|o| o.is_listed() || std::ptr::eq(**o, m))
+                .filter(|o| series.is_none() || o.series() == series)
+                .collect();
+            let pos = siblings.iter().position(|o| std::ptr::eq(**o, m)).unwrap();
+
+            let mut related: Vec<_> = (0..metas.len())
+                .filter(|&j| j != i && metas[j].is_listed() && metas[j].lang() == m.lang())
+                .filter_map(|j| relevance(i, j).map(|r| (r, metas[j])))
+                .collect();
+            related.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+            Navigation {
+                prev: pos.checked_sub(1).map(|i| *siblings[i]),
+                next: siblings.get(pos + 1).map(|o| **o),
+                series: series.map(|s| &s[..]),
+                related: related
+                    .into_iter()
+                    .take(MAX_RELATED)
+                    .map(|(_, o)| o)
+                    .collect(),
+                backlinks: backlinks[i].iter().map(|&j| metas[j]).collect(),
+            }
+        })
+        .collect()
+}
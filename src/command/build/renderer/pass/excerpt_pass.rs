@@ -0,0 +1,133 @@
+use crate::command::build::renderer::context::Context;
+use pulldown_cmark::{Event, Tag, TagEnd};
+
+/// 抜粋の最大文字数
+const MAX_EXCERPT_CHARS: usize = 120;
+
+/// 抜粋の区切りを明示するマーカー
+const MORE_MARKER: &str = "<!-- more -->";
+
+fn is_more_marker(e: &Event) -> bool {
+    matches!(e, Event::Html(h) | Event::InlineHtml(h) if h.trim() == MORE_MARKER)
+}
+
+/// テキストを `max_chars` 文字以内に切り詰めます。
+/// 単語の途中で切らないよう、 `util::segment` による分割の境界で切ります。
+/// 範囲内に句点があれば、そこで切ります。
+fn truncate_text(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_owned();
+    }
+
+    let mut out = String::new();
+    let mut num_chars = 0;
+    for w in crate::util::segment(text) {
+        let len = w.chars().count();
+        if num_chars + len > max_chars {
+            break;
+        }
+        out.push_str(w);
+        num_chars += len;
+    }
+
+    // 後半に文末があれば、そこで切る
+    let sentence_end = out
+        .char_indices()
+        .filter(|(_, c)| matches!(c, '。' | '．' | '！' | '？'))
+        .map(|(i, c)| i + c.len_utf8())
+        .next_back()
+        .filter(|&i| i >= out.len() / 2);
+
+    match sentence_end {
+        Some(i) => out[..i].to_owned(),
+        None => format!("{}…", out.trim_end()),
+    }
+}
+
+/// 記事の抜粋を作成します。
+/// front-matter で `description` が指定されている場合は何もしません。
+/// `<!-- more -->` がある場合はそれより前の段落を、ない場合は先頭の段落を切り詰めて使います。
+pub fn excerpt_pass<'a>(
+    events: Vec<Event<'a>>,
+    ctxt: &mut Context,
+) -> anyhow::Result<Vec<Event<'a>>> {
+    if ctxt.description().is_ok() {
+        return Ok(events);
+    }
+
+    let has_marker = events.iter().any(is_more_marker);
+
+    let mut paragraphs = Vec::new();
+    let mut paragraph: Option<String> = None;
+    let mut num_chars = 0;
+    let mut in_image = false;
+    for e in &events {
+        if is_more_marker(e) {
+            break;
+        }
+        match e {
+            Event::Start(Tag::Paragraph) => paragraph = Some(String::new()),
+            // 画像の代替テキストは本文ではないので無視する
+            Event::Start(Tag::Image { .. }) => in_image = true,
+            Event::End(TagEnd::Image) => in_image = false,
+            Event::End(TagEnd::Paragraph) => {
+                let Some(p) = paragraph.take() else { continue };
+                let p = p.trim().to_owned();
+                if p.is_empty() {
+                    continue;
+                }
+                num_chars += p.chars().count();
+                paragraphs.push(p);
+                if !has_marker && num_chars >= MAX_EXCERPT_CHARS {
+                    break;
+                }
+            }
+            Event::Text(t) | Event::Code(t) | Event::InlineMath(t) if !in_image => {
+                if let Some(p) = paragraph.as_mut() {
+                    p.push_str(t);
+                }
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                if let Some(p) = paragraph.as_mut() {
+                    p.push(' ');
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let text = paragraphs.join(" ");
+    if text.is_empty() {
+        return Ok(events);
+    }
+
+    let excerpt = if has_marker {
+        text
+    } else {
+        truncate_text(&text, MAX_EXCERPT_CHARS)
+    };
+    ctxt.set_description(excerpt);
+
+    Ok(events)
+}
+
+#[cfg(test)]
+mod test {
+    use super::truncate_text;
+
+    #[test]
+    fn test_truncate_text() {
+        assert_eq!(truncate_text("短い文章", 10), "短い文章");
+        assert_eq!(
+            truncate_text("今日はいい天気です。明日も晴れるでしょう。", 15),
+            "今日はいい天気です。"
+        );
+        assert!(
+            truncate_text(
+                "メロスは激怒した必ずかの邪智暴虐の王を除かなければならぬと決意した",
+                10
+            )
+            .ends_with('…')
+        );
+    }
+}
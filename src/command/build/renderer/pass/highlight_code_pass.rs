@@ -38,22 +38,20 @@ pub fn highlight_code_pass<'a>(
             Event::End(TagEnd::CodeBlock) => {
                 is_code_block = false;
             }
-            Event::Text(t) => {
-                if is_code_block {
-                    let code = t.to_string();
+            Event::Text(t) if is_code_block => {
+                let code = t.to_string();
 
-                    let mut code = code
-                        .to_string()
-                        .replace('&', "&amp;")
-                        .replace('<', "&lt;")
-                        .replace('>', "&gt;");
+                let mut code = code
+                    .to_string()
+                    .replace('&', "&amp;")
+                    .replace('<', "&lt;")
+                    .replace('>', "&gt;");
 
-                    for m in macros {
-                        code = m.replace_all(&code).to_string();
-                    }
-
-                    *e = Event::InlineHtml(code.into());
+                for m in macros {
+                    code = m.replace_all(&code).to_string();
                 }
+
+                *e = Event::InlineHtml(code.into());
             }
             _ => {}
         }
@@ -25,7 +25,7 @@ fn make_image_tag(
 
 pub fn image_convert_pass<'a>(
     events: Vec<Event<'a>>,
-    _: &mut Context,
+    _ctxt: &mut Context,
 ) -> anyhow::Result<Vec<Event<'a>>> {
     let mut url = None;
     let mut title = None;
@@ -48,6 +48,7 @@ pub fn image_convert_pass<'a>(
         }
         Event::End(TagEnd::Image) if url.is_some() => {
             let (url, title, alt) = (url.take().unwrap(), title.take(), alt.take());
+
             let img_tag = make_image_tag(&url, &alt, &title);
 
             let figcaption_tag = alt
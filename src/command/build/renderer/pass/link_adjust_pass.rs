@@ -1,18 +1,192 @@
 use crate::command::build::renderer::context::Context;
-use pulldown_cmark::{Event, Tag};
+use crate::util::{LinkStyle, PathExt as _, decode_url_path, encode_url_path};
+use pulldown_cmark::{CowStr, Event, Tag};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// 記事のソースと出力先のディレクトリ、記事の出力先の対応
+struct Locations<'a> {
+    /// `src/` から記事のソースファイルのディレクトリへの相対パス
+    src_dir: &'a Path,
+    /// ルートから記事の出力先のディレクトリへの相対パス
+    page_dir: &'a Path,
+    /// `src/` からのソースファイルのパスと、ルートからの出力先のパスの対応
+    permalinks: &'a HashMap<PathBuf, PathBuf>,
+    /// リンクに書く URL の形式
+    link_style: &'a LinkStyle,
+}
+
+impl Locations<'_> {
+    /// ソースのディレクトリと出力先のディレクトリが異なるか
+    fn relocated(&self) -> bool {
+        self.src_dir != self.page_dir
+    }
+
+    /// ソースファイルからの相対パスを、ルートからのパスに変換します。
+    fn resolve(&self, path: &str) -> PathBuf {
+        match path.strip_prefix('/') {
+            Some(p) => Path::new(p).normalized(),
+            None => self.src_dir.join(path).normalized(),
+        }
+    }
+
+    /// 他の記事の出力先を返します。
+    /// `target` はソースファイル (`.md`) または出力先 (`.html`) のルートからのパスです。
+    fn page_dst(&self, target: &Path) -> PathBuf {
+        let src = target.with_extension("md");
+        match self.permalinks.get(&src) {
+            Some(dst) => dst.clone(),
+            None => target.with_extension("html"),
+        }
+    }
+
+    /// ルートからのパスを、リンクに書く形式に変換します。
+    /// 元のリンクがルートからの絶対パスの場合は、サイトのベースパスからの絶対パスにします。
+    fn to_url(&self, original: &str, target: &Path) -> String {
+        if original.starts_with('/') {
+            self.link_style.with_base_path(target.to_str().unwrap())
+        } else {
+            target
+                .relative_from(self.page_dir)
+                .to_str()
+                .unwrap()
+                .to_owned()
+        }
+    }
+
+    /// 他の記事へのリンクを返します。
+    /// 空白や日本語などを含むパスはパーセントエンコードします。
+    fn to_page_url(&self, original: &str, dst: &Path) -> String {
+        encode_url_path(&self.link_style.href_of(self.to_url(original, dst)))
+    }
+}
+
+/// リンク先を調整します。
+/// 他の記事へのリンク (`.md`) は、リンク先の記事の出力先へのリンクに変換します。
+/// 記事の出力先がソースファイルと異なるディレクトリの場合、相対パスを出力先からの相対パスに変換します。
+fn adjust_url(url: &mut CowStr, is_image: bool, loc: &Locations, ctxt: &mut Context) {
+    // http:, mailto: などのスキームを持つ URL と、 `//` で始まる別ホストの URL は変換しない
+    if url.contains(':') || url.starts_with("//") {
+        return;
+    }
+
+    let (path, fragment) = match url.find('#') {
+        Some(i) => url.split_at(i),
+        None => (&url[..], ""),
+    };
+    if path.is_empty() {
+        return;
+    }
+
+    let is_page = !is_image && {
+        let p = Path::new(path);
+        p.extension_is("md") || p.extension_is("html")
+    };
+
+    // 他の記事へのリンクと、参照しているファイルを記録する
+    let path = if is_page {
+        // パーセントエンコードされたリンク (`%E6%97%A5%E8%A8%98.md`) も、ファイルのパスに戻して探す
+        let target = loc.resolve(&decode_url_path(path));
+        let dst = loc.page_dst(&target);
+        let unchanged = !loc.relocated() && dst == target.with_extension("html");
+        let path = if unchanged && !path.starts_with('/') {
+            // 出力先が変わらない場合は、書かれたリンクをなるべくそのまま使う
+            match path.strip_suffix(".md") {
+                Some(stem) => format!("{stem}.html"),
+                None => path.to_owned(),
+            }
+        } else {
+            loc.to_page_url(path, &dst)
+        };
+        ctxt.push_link(dst);
+        path
+    } else {
+        // 代表画像の指定がなければ、最初の画像を使う
+        // ベースパスを付ける前の、ルートからの絶対パスで記録する
+        if is_image && ctxt.image().is_err() {
+            let image = loc.resolve(&decode_url_path(path));
+            ctxt.set_image(format!("/{}", image.to_str().unwrap()));
+        }
+        ctxt.push_asset_url(path);
+        if path.starts_with('/') || loc.relocated() {
+            loc.to_url(path, &loc.resolve(path))
+        } else {
+            path.to_owned()
+        }
+    };
+
+    *url = format!("{path}{fragment}").into();
+}
 
 pub fn link_adjust_pass<'a>(
     mut events: Vec<Event<'a>>,
-    _: &mut Context,
+    ctxt: &mut Context,
 ) -> anyhow::Result<Vec<Event<'a>>> {
-    events.iter_mut().for_each(|mut e| {
-        if let Event::Start(Tag::Link { dest_url: url, .. }) = &mut e {
-            let is_local = !url.starts_with("http://") && !url.starts_with("https://");
-            let is_md = url.ends_with(".md");
-            if is_local && is_md {
-                *url = format!("{}.html", &url[..url.len() - ".md".len()]).into();
-            }
-        }
+    let page_dir = ctxt.build_root_to_dst()?.parent().unwrap().to_owned();
+    let src_dir = ctxt.src_root_to_src()?.parent().unwrap().to_owned();
+    let permalinks = ctxt.permalinks.clone();
+    let link_style = ctxt.link_style.clone();
+    let loc = Locations {
+        src_dir: &src_dir,
+        page_dir: &page_dir,
+        permalinks: &permalinks,
+        link_style: &link_style,
+    };
+
+    events.iter_mut().for_each(|e| match e {
+        Event::Start(Tag::Link { dest_url, .. }) => adjust_url(dest_url, false, &loc, ctxt),
+        Event::Start(Tag::Image { dest_url, .. }) => adjust_url(dest_url, true, &loc, ctxt),
+        _ => {}
     });
     Ok(events)
 }
+
+#[cfg(test)]
+mod test {
+    use super::link_adjust_pass;
+    use crate::command::build::renderer::context::Context;
+    use pulldown_cmark::{Event, Parser, Tag};
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_encoded_page_link() {
+        let mut ctxt = Context::default();
+        ctxt.set_src_root_to_src(PathBuf::from("public/foo.md"));
+        ctxt.set_build_root_to_dst(PathBuf::from("public/foo.html"));
+        ctxt.permalinks = Arc::new(HashMap::from([(
+            PathBuf::from("public/日記.md"),
+            PathBuf::from("2024/diary.html"),
+        )]));
+
+        let markdown = "[a](%E6%97%A5%E8%A8%98.md#top) [b](日記.md) [c](%E3%83%A1%E3%83%A2.md)";
+        let events: Vec<_> = Parser::new(markdown).collect();
+        let events = link_adjust_pass(events, &mut ctxt).unwrap();
+        let urls: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                Event::Start(Tag::Link { dest_url, .. }) => Some(dest_url.to_string()),
+                _ => None,
+            })
+            .collect();
+
+        // エンコードされたリンクも、記事の出力先へのリンクに変換する
+        assert_eq!(
+            urls,
+            [
+                "../2024/diary.html#top",
+                "../2024/diary.html",
+                "%E3%83%A1%E3%83%A2.html"
+            ]
+        );
+        assert_eq!(
+            ctxt.links(),
+            &[
+                PathBuf::from("2024/diary.html"),
+                PathBuf::from("2024/diary.html"),
+                PathBuf::from("public/メモ.html"),
+            ]
+        );
+    }
+}
@@ -1,7 +1,8 @@
 use super::HighlightRule;
 use crate::command::build::renderer::context::Context;
+use crate::util::PageDate;
 use MetadataBlockKind::YamlStyle;
-use anyhow::bail;
+use anyhow::{Context as _, bail};
 use pulldown_cmark::{Event, Tag};
 use pulldown_cmark::{MetadataBlockKind, TagEnd};
 use serde::Deserialize;
@@ -22,8 +23,19 @@ pub fn read_header_pass(events: &mut Vec<Event>, ctxt: &mut Context) -> anyhow::
     };
 
     let header: YamlHeader = serde_yaml::from_str(header)?;
-    ctxt.set_create_date(header.create_date);
-    ctxt.set_last_update_date(header.last_update_date);
+    let tz = ctxt.timezone;
+    let parse_date = |key: &str, s: &str| {
+        PageDate::parse(s, tz).with_context(|| format!("Invalid `{key}` in yaml header"))
+    };
+    // 省略された日付は、設定に応じて呼び出し元で補う
+    if let Some(date) = header.create_date {
+        let date = parse_date("create", &date)?;
+        ctxt.set_create_date(date);
+    }
+    if let Some(date) = header.last_update_date {
+        let date = parse_date("update", &date)?;
+        ctxt.set_last_update_date(date);
+    }
     ctxt.set_tags(header.tags);
     if let Some(h) = header.highlights {
         ctxt.set_highlights(h);
@@ -31,19 +43,43 @@ pub fn read_header_pass(events: &mut Vec<Event>, ctxt: &mut Context) -> anyhow::
     if let Some(pwd) = header.password {
         ctxt.set_password(pwd);
     }
+    if let Some(description) = header.description {
+        ctxt.set_description(description);
+    }
+    if let Some(series) = header.series {
+        ctxt.set_series(series);
+    }
+    if let Some(image) = header.image {
+        ctxt.set_image(image);
+    }
+    ctxt.exclude_from_search = !header.search;
+    ctxt.is_draft |= header.draft;
+    ctxt.history = header.history;
+    ctxt.publish_date = header
+        .publish_date
+        .map(|date| parse_date("publish", &date))
+        .transpose()?;
+    ctxt.expire_date = header
+        .expire_date
+        .map(|date| parse_date("expire", &date))
+        .transpose()?;
+    ctxt.slug = header.slug;
+    ctxt.permalink = header.permalink;
+    ctxt.aliases = header.aliases;
+    ctxt.lang = header.lang;
 
     Ok(())
 }
 
 #[derive(Deserialize, Debug)]
 struct YamlHeader {
-    /// 記事の作成日
+    /// 記事の作成日 (yyyy-MM-dd または RFC 3339)
     #[serde(rename = "create")]
-    pub create_date: String,
+    pub create_date: Option<String>,
 
-    /// 記事の最終更新日
+    /// 記事の最終更新日 (yyyy-MM-dd または RFC 3339)
     #[serde(rename = "update")]
-    pub last_update_date: String,
+    pub last_update_date: Option<String>,
 
     /// 記事につけられたタグ
     #[serde(default)]
@@ -53,7 +89,54 @@ struct YamlHeader {
     /// 暗号化時のパスワード
     pub password: Option<String>,
 
+    /// 記事の概要
+    pub description: Option<String>,
+
+    /// 記事を代表する画像 (OGP 用)
+    pub image: Option<String>,
+
+    /// 記事が属するシリーズ
+    pub series: Option<String>,
+
     /// コードハイライトのルール
     #[serde(alias = "highlight")]
     pub highlights: Option<Vec<HighlightRule>>,
+
+    /// サイト内検索の対象にするか
+    #[serde(default = "default_search")]
+    pub search: bool,
+
+    /// 下書きか
+    #[serde(default)]
+    pub draft: bool,
+
+    /// 変更履歴のページを出力するか
+    #[serde(default)]
+    pub history: bool,
+
+    /// 記事を公開する日時 (yyyy-MM-dd または RFC 3339)
+    #[serde(rename = "publish")]
+    pub publish_date: Option<String>,
+
+    /// 記事の公開期限 (yyyy-MM-dd または RFC 3339)
+    #[serde(rename = "expire")]
+    pub expire_date: Option<String>,
+
+    /// 出力先のファイル名 (拡張子を除く) またはパターンの `:slug`
+    pub slug: Option<String>,
+
+    /// 出力先のパスまたはパターン (`zakki.toml` の `permalink` より優先)
+    pub permalink: Option<String>,
+
+    /// 記事へ転送するページを出力するパス
+    #[serde(default)]
+    #[serde(alias = "alias")]
+    pub aliases: Vec<String>,
+
+    /// 記事の言語 (省略時はファイル名の `foo.en.md` またはサイトの既定の言語)
+    pub lang: Option<String>,
+}
+
+fn default_search() -> bool {
+    true
 }
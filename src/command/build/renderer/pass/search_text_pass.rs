@@ -0,0 +1,124 @@
+use crate::command::build::renderer::context::Context;
+use pulldown_cmark::{Event, Tag, TagEnd};
+
+/// 索引から除外する範囲の開始を表すマーカー
+const SEARCH_OFF_MARKER: &str = "<!-- search:off -->";
+
+/// 索引から除外する範囲の終了を表すマーカー
+const SEARCH_ON_MARKER: &str = "<!-- search:on -->";
+
+/// 文字を含まない (数値や記号のみからなる) か
+fn has_no_letter(s: &str) -> bool {
+    !s.chars().any(char::is_alphabetic)
+}
+
+/// サイト内検索の索引に登録する本文のテキストを作成します。
+/// 本文のうち、次のものは索引に含めません。
+/// - コードブロック
+/// - 数値や記号のみからなる表のセル
+/// - `<!-- search:off -->` から `<!-- search:on -->` までの範囲
+///
+/// 数式は LaTeX のソースを索引に含めます。
+/// そのため、数式を HTML に変換するパスより前に実行する必要があります。
+pub fn search_text_pass<'a>(
+    events: Vec<Event<'a>>,
+    ctxt: &mut Context,
+) -> anyhow::Result<Vec<Event<'a>>> {
+    // 暗号化する記事の本文は公開しない (タイトルは公開されているので含める)
+    if ctxt.to_encrypt {
+        ctxt.set_text(ctxt.title()?.clone());
+        return Ok(events);
+    }
+    if ctxt.exclude_from_search {
+        ctxt.set_text(String::new());
+        return Ok(events);
+    }
+
+    let mut text = String::new();
+    let mut cell: Option<String> = None;
+    let mut enabled = true;
+    let mut in_code_block = false;
+    let mut in_metadata = false;
+    for e in &events {
+        match e {
+            Event::Html(h) | Event::InlineHtml(h) => match h.trim() {
+                SEARCH_OFF_MARKER => enabled = false,
+                SEARCH_ON_MARKER => enabled = true,
+                _ => {}
+            },
+            Event::Start(Tag::MetadataBlock(_)) => in_metadata = true,
+            Event::End(TagEnd::MetadataBlock(_)) => in_metadata = false,
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Start(Tag::TableCell) => cell = Some(String::new()),
+            Event::End(TagEnd::TableCell) => {
+                let c = cell.take().unwrap_or_default();
+                if !has_no_letter(&c) {
+                    text.push_str(&c);
+                    text.push(' ');
+                }
+            }
+            _ if !enabled || in_code_block || in_metadata => {}
+            Event::Text(t) | Event::Code(t) => match cell.as_mut() {
+                Some(c) => c.push_str(t),
+                None => text.push_str(t),
+            },
+            Event::InlineMath(latex) | Event::DisplayMath(latex) => {
+                let s = format!(" {latex} ");
+                match cell.as_mut() {
+                    Some(c) => c.push_str(&s),
+                    None => text.push_str(&s),
+                }
+            }
+            // ブロックの区切りで単語が連結されないようにする
+            Event::End(_) | Event::SoftBreak | Event::HardBreak | Event::Rule => text.push(' '),
+            _ => {}
+        }
+    }
+
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    ctxt.set_text(text);
+
+    Ok(events)
+}
+
+#[cfg(test)]
+mod test {
+    use super::search_text_pass;
+    use crate::command::build::renderer::context::Context;
+    use pulldown_cmark::{Options, Parser};
+
+    fn search_text(markdown: &str) -> String {
+        let mut ctxt = Context::default();
+        let events = Parser::new_ext(markdown, Options::all()).collect();
+        search_text_pass(events, &mut ctxt).unwrap();
+        ctxt.text().unwrap().clone()
+    }
+
+    #[test]
+    fn test_search_text_pass() {
+        let markdown = r#"
+# タイトル
+
+本文の`コード`です。
+
+```rust
+fn main() {}
+```
+
+| 名前 | 値 |
+| --- | --- |
+| 円周率 | 3.14 |
+
+<!-- search:off -->
+索引に含めない段落
+<!-- search:on -->
+
+数式 $e^{i\pi}$ も含める。
+"#;
+        assert_eq!(
+            search_text(markdown),
+            "タイトル 本文のコードです。 名前 値 円周率 数式 e^{i\\pi} も含める。"
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use crate::util::{PageDate, PathExt as _};
+use anyhow::{Result, bail};
+use regex::{Captures, Regex};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// パターン中の変数 (`:year` など)
+static VARIABLE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r":([a-z]+)").unwrap());
+
+/// パターンが記事の日付を使うか
+pub fn uses_date(pattern: &str) -> bool {
+    VARIABLE
+        .captures_iter(pattern)
+        .any(|c| matches!(&c[1], "year" | "month" | "day"))
+}
+
+/// 出力先のパターンの展開に使う記事の情報
+pub struct PermalinkVars<'a> {
+    /// 記事の作成日 (パターンが日付を使わない場合は `None`)
+    pub date: Option<&'a PageDate>,
+    /// 記事のスラッグ
+    pub slug: &'a str,
+    /// `src/` から記事のディレクトリへの相対パス
+    pub dir: &'a Path,
+    /// 記事の言語
+    pub lang: &'a str,
+}
+
+/// 出力先のパターンを展開し、ルートからの相対パスを返します。
+/// `/` で始まらないパターンは、記事のディレクトリからの相対パスとして扱います。
+pub fn expand_permalink(pattern: &str, vars: &PermalinkVars) -> Result<PathBuf> {
+    let mut unknown = None;
+    let path = VARIABLE.replace_all(pattern, |caps: &Captures| {
+        let date = vars.date;
+        match &caps[1] {
+            "year" => date.map(|d| format!("{:04}", d.year())),
+            "month" => date.map(|d| format!("{:02}", d.month())),
+            "day" => date.map(|d| format!("{:02}", d.day())),
+            "slug" => Some(vars.slug.to_owned()),
+            "dir" => Some(vars.dir.to_str().unwrap().to_owned()),
+            "lang" => Some(vars.lang.to_owned()),
+            _ => None,
+        }
+        .unwrap_or_else(|| {
+            unknown.get_or_insert_with(|| caps[0].to_owned());
+            String::new()
+        })
+    });
+    if let Some(unknown) = unknown {
+        bail!(
+            "Unknown variable `{unknown}` in permalink `{pattern}` (available: :year, :month, :day, :slug, :dir, :lang)."
+        );
+    }
+
+    resolve_output_path(&path, vars.dir)
+}
+
+/// 出力先のパスを、ルートからの相対パスに変換します。
+/// `/` で始まるパスはルートから、それ以外は `base_dir` からの相対パスとして扱います。
+/// `/` で終わるパスは、そのディレクトリの `index.html` とします。
+pub fn resolve_output_path(path: &str, base_dir: &Path) -> Result<PathBuf> {
+    let mut resolved = match path.strip_prefix('/') {
+        Some(p) => Path::new(p.trim_start_matches('/')).normalized(),
+        None => base_dir.join(path).normalized(),
+    };
+    if path.ends_with('/') {
+        resolved.push("index.html");
+    }
+
+    if resolved.starts_with("..") || resolved.has_root() {
+        bail!("`{path}` is outside the build directory.");
+    }
+    if !resolved.extension_is("html") {
+        bail!("`{path}` must end with `.html` or `/`.");
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PermalinkVars, expand_permalink, resolve_output_path};
+    use crate::util::PageDate;
+    use chrono_tz::Tz;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_expand_permalink() {
+        let date = PageDate::parse("2024-05-03", Tz::UTC).unwrap();
+        let vars = PermalinkVars {
+            date: Some(&date),
+            slug: "hello",
+            dir: Path::new("public/blog"),
+            lang: "en",
+        };
+
+        let expand = |pattern| expand_permalink(pattern, &vars).unwrap();
+        assert_eq!(
+            expand("/:year/:slug.html"),
+            PathBuf::from("2024/hello.html")
+        );
+        assert_eq!(
+            expand("/:year/:month/:day/:slug/"),
+            PathBuf::from("2024/05/03/hello/index.html")
+        );
+        assert_eq!(
+            expand("/:dir/:slug.html"),
+            PathBuf::from("public/blog/hello.html")
+        );
+        assert_eq!(expand("../:slug.html"), PathBuf::from("public/hello.html"));
+        assert_eq!(expand("/:lang/:slug.html"), PathBuf::from("en/hello.html"));
+
+        assert!(expand_permalink("/:title.html", &vars).is_err());
+        assert!(expand_permalink("/:slug", &vars).is_err());
+        assert!(expand_permalink("../../../:slug.html", &vars).is_err());
+    }
+
+    #[test]
+    fn test_resolve_output_path() {
+        let dir = Path::new("public");
+        let resolve = |path| resolve_output_path(path, dir).unwrap();
+        assert_eq!(resolve("old.html"), PathBuf::from("public/old.html"));
+        assert_eq!(resolve("/2023/old/"), PathBuf::from("2023/old/index.html"));
+        assert_eq!(resolve("//old.html"), PathBuf::from("old.html"));
+    }
+}
@@ -0,0 +1,15 @@
+use super::goto_zakki_root;
+use crate::config::FileConfig;
+use anyhow::Result;
+
+/// zakki.toml を検証し、 `show` が真の場合は既定値を補った設定を表示します。
+pub fn config(show: bool) -> Result<()> {
+    goto_zakki_root()?;
+    let cfg = FileConfig::load()?;
+    if show {
+        print!("{}", cfg.effective_toml()?);
+    } else {
+        println!("zakki.toml is valid.");
+    }
+    Ok(())
+}
@@ -0,0 +1,231 @@
+use super::build::{Metadata, collect_metadatas, load_config, search_index};
+use crate::config::SearchEngine;
+use crate::util::{QueryDocument, QueryTerm, SearchIndex, SearchQuery, TermKind, Tokenizer};
+use anyhow::{Result, bail};
+use itertools::Itertools;
+
+/// クライアント (script.js) と同じ方法で記事を検索します。
+struct Searcher<'a> {
+    metas: &'a [Metadata],
+    tokenizer: &'a Tokenizer,
+    /// 転置索引 (Bloom filter で検索する場合は `None`)
+    index: Option<SearchIndex>,
+}
+
+impl<'a> Searcher<'a> {
+    fn new(engine: SearchEngine, metas: &'a [Metadata], tokenizer: &'a Tokenizer) -> Self {
+        let index = (engine == SearchEngine::Inverted).then(|| search_index(metas, tokenizer));
+        Self {
+            metas,
+            tokenizer,
+            index,
+        }
+    }
+
+    fn has_word(&self, doc: usize, word: &str) -> bool {
+        match &self.index {
+            Some(index) => index.contains(word, doc),
+            None => self.metas[doc].bloom_filter().contains(word),
+        }
+    }
+
+    /// クエリの条件を評価し、 `f` に渡します。
+    fn with_document<T>(&self, doc: usize, f: impl FnOnce(&QueryDocument) -> T) -> T {
+        let meta = &self.metas[doc];
+        let has_word = |w: &str| self.has_word(doc, w);
+        let create = meta.create().to_string();
+        f(&QueryDocument {
+            tags: meta.tags(),
+            create: &create,
+            path: meta.path().to_str().unwrap(),
+            // Bloom filter による検索では本文を保持しない
+            text: self.index.as_ref().map(|_| meta.text()),
+            has_word: &has_word,
+        })
+    }
+
+    /// 単語に対するスコアを (文書番号, スコア) の列で返します。
+    fn scores(&self, words: &[String]) -> Vec<(usize, f64)> {
+        if words.is_empty() {
+            return (0..self.metas.len()).map(|i| (i, 0.0)).collect();
+        }
+
+        match &self.index {
+            Some(index) => index.search(words),
+            None => (0..self.metas.len())
+                .map(|i| (i, self.hit_rate(i, words)))
+                .filter(|&(_, r)| r > 0.0)
+                .collect(),
+        }
+    }
+
+    /// Bloom filter に含まれる単語の割合
+    fn hit_rate(&self, doc: usize, words: &[String]) -> f64 {
+        let filter = self.metas[doc].bloom_filter();
+        let hits = words.iter().filter(|w| filter.contains(w)).count();
+        hits as f64 / words.len() as f64
+    }
+
+    /// 検索結果を (文書番号, スコア) のスコアの高い順で返します。
+    fn search(&self, query: &SearchQuery, words: &[String]) -> Vec<(usize, f64)> {
+        let mut hits: Vec<_> = self
+            .scores(words)
+            .into_iter()
+            .filter(|&(i, _)| !self.metas[i].exclude_from_search())
+            .filter(|&(i, _)| self.with_document(i, |d| query.matches(d, self.tokenizer)))
+            .collect();
+        hits.sort_by(|(a, x), (b, y)| y.total_cmp(x).then(a.cmp(b)));
+        hits
+    }
+
+    /// 単語が記事のスコアにどう寄与したかを説明します。
+    fn explain_word(&self, doc: usize, word: &str) -> String {
+        match &self.index {
+            Some(index) => match index.word_scores(word).get(&doc) {
+                Some((term, score)) if *term == word => format!("matched (score {score:.3})"),
+                Some((term, score)) => format!("matched \"{term}\" by prefix (score {score:.3})"),
+                None => "not found".to_owned(),
+            },
+            None => {
+                let hit = self.metas[doc].bloom_filter().contains(word);
+                let actual = self.metas[doc].words().contains(word);
+                match (hit, actual) {
+                    (true, true) => "hit".to_owned(),
+                    (true, false) => "hit (false positive)".to_owned(),
+                    (false, _) => "miss".to_owned(),
+                }
+            }
+        }
+    }
+
+    /// 記事が検索結果に含まれる (含まれない) 理由を説明します。
+    fn explain(&self, doc: usize, query: &SearchQuery) -> Vec<String> {
+        let meta = &self.metas[doc];
+        let mut lines = Vec::new();
+        for term in query.terms() {
+            let ok = self.with_document(doc, |d| term.matches(d, self.tokenizer));
+            let mark = if ok { "ok" } else { "NG" };
+            let label = describe(term);
+            let detail = match term.kind {
+                TermKind::Word | TermKind::Phrase if !term.negated => {
+                    self.tokenizer.tokenize(&term.value).iter().for_each(|w| {
+                        lines.push(format!("{w:?}: {}", self.explain_word(doc, w)));
+                    });
+                    if term.kind == TermKind::Word {
+                        continue;
+                    }
+                    String::new()
+                }
+                TermKind::Word | TermKind::Phrase => String::new(),
+                TermKind::Tag => format!(" (tags: {})", meta.tags().join(", ")),
+                TermKind::After => format!(" (created: {})", meta.create()),
+                TermKind::Section => format!(" (path: {})", meta.path().display()),
+            };
+            lines.push(format!("[{mark}] {label}{detail}"));
+        }
+        lines
+    }
+}
+
+/// クエリの条件を、入力された形式で表します。
+fn describe(term: &QueryTerm) -> String {
+    let neg = if term.negated { "-" } else { "" };
+    let value = &term.value;
+    match term.kind {
+        TermKind::Word => format!("{neg}{value}"),
+        TermKind::Phrase => format!("{neg}\"{value}\""),
+        TermKind::Tag => format!("{neg}tag:\"{value}\""),
+        TermKind::After => format!("{neg}after:{value}"),
+        TermKind::Section => format!("{neg}section:\"{value}\""),
+    }
+}
+
+pub fn search(
+    query: &str,
+    render_draft: bool,
+    engine: Option<SearchEngine>,
+    explain: bool,
+    lang: Option<&str>,
+) -> Result<()> {
+    let cfg = load_config(render_draft)?;
+    let engine = engine.unwrap_or(cfg.search_engine());
+    // クライアントと同じく、索引は言語ごとに作り、同じ言語の記事だけを検索する
+    let lang = lang.unwrap_or(&cfg.default_locale().lang).to_owned();
+    if cfg.locales().iter().all(|l| l.lang != lang) {
+        bail!("`{lang}` is not in `languages`.");
+    }
+    let metas: Vec<_> = collect_metadatas(&cfg)?
+        .into_iter()
+        .filter(|m| m.lang() == lang)
+        .collect();
+    let searcher = Searcher::new(engine, &metas, cfg.tokenizer());
+
+    let query = SearchQuery::parse(query);
+    let words: Vec<_> = query
+        .ranking_words(cfg.tokenizer())
+        .into_iter()
+        .unique()
+        .collect();
+
+    println!("engine: {}", engine.as_str());
+    println!("lang:   {lang}");
+    println!("terms:  {}", query.terms().iter().map(describe).join(" "));
+    println!(
+        "words:  {}",
+        words.iter().map(|w| format!("{w:?}")).join(" ")
+    );
+    println!();
+
+    let hits = searcher.search(&query, &words);
+    let score_label = match engine {
+        SearchEngine::Bloom => "match rate",
+        SearchEngine::Inverted => "score",
+    };
+    for (rank, (i, score)) in hits.iter().enumerate() {
+        let meta = &metas[*i];
+        println!(
+            "{:>3}. {} ({}) {score_label}: {score:.3}",
+            rank + 1,
+            meta.title(),
+            meta.path().display(),
+        );
+        if explain {
+            searcher
+                .explain(*i, &query)
+                .iter()
+                .for_each(|l| println!("       {l}"));
+        }
+    }
+    if hits.is_empty() {
+        println!("No pages matched.");
+    }
+
+    if explain {
+        // 検索結果に含まれなかった記事についても理由を表示する
+        let misses: Vec<_> = (0..metas.len())
+            .filter(|i| hits.iter().all(|(j, _)| i != j))
+            .collect();
+        if !misses.is_empty() {
+            println!();
+            println!("not matched:");
+        }
+        for i in misses {
+            let meta = &metas[i];
+            println!("   - {} ({})", meta.title(), meta.path().display());
+            if meta.is_draft() && meta.exclude_from_search() {
+                println!("       excluded from search (draft)");
+                continue;
+            }
+            if meta.exclude_from_search() {
+                println!("       excluded from search (search: false)");
+                continue;
+            }
+            searcher
+                .explain(i, &query)
+                .iter()
+                .for_each(|l| println!("       {l}"));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use super::build::{Metadata, bloom_filter_js, collect_metadatas, load_config, search_index_files};
+use crate::config::Config;
+use anyhow::Result;
+use itertools::Itertools;
+use rayon::prelude::*;
+use std::collections::BTreeSet;
+
+/// 偽陽性率の計測に使う単語数の上限
+const MAX_HELD_OUT_WORDS: usize = 10000;
+
+/// 索引の大部分を占めるとみなす、フィルターサイズの中央値に対する倍率
+const LARGE_PAGE_FACTOR: usize = 4;
+
+/// 索引の大部分を占めるとみなす、全フィルターに占める割合
+const LARGE_PAGE_SHARE: f64 = 0.1;
+
+/// 索引の大部分を占めるとみなすフィルターの最小バイト数
+/// 記事が少ないサイトで、小さな記事まで報告しないようにする
+const LARGE_PAGE_MIN_BYTES: usize = 4096;
+
+/// バイト数を読みやすい形式で返します。
+fn human_size(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{bytes} B")
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MiB", bytes as f64 / 1024.0 / 1024.0)
+    }
+}
+
+/// 記事の Bloom filter の偽陽性率を計測します。
+/// サイト内の他の記事にだけ現れる単語を問い合わせ、誤って含まれると判定された割合を返します。
+/// 該当する単語がない場合は `None` を返します。
+fn measured_fp(meta: &Metadata, vocabulary: &[&str]) -> Option<f64> {
+    let held_out: Vec<_> = vocabulary
+        .iter()
+        .filter(|w| !meta.words().contains(**w))
+        .collect();
+    if held_out.is_empty() {
+        return None;
+    }
+
+    let filter = meta.bloom_filter();
+    let false_positives = held_out.iter().filter(|w| filter.contains(w)).count();
+    Some(false_positives as f64 / held_out.len() as f64)
+}
+
+/// 偽陽性率の計測に使う、サイト全体の語彙を返します。
+/// 語彙が多い場合は、一定の間隔で間引きます。
+fn vocabulary(metas: &[Metadata]) -> Vec<&str> {
+    let all: BTreeSet<&str> = metas
+        .iter()
+        .flat_map(|m| m.words().iter().map(|w| &w[..]))
+        .collect();
+    let step = all.len().div_ceil(MAX_HELD_OUT_WORDS).max(1);
+    all.into_iter().step_by(step).collect()
+}
+
+fn print_search_stats(cfg: &Config, metas: &[Metadata]) -> Result<()> {
+    println!("search engine: {}", cfg.search_engine().as_str());
+    println!("search_fp: {}", cfg.search_fp());
+    println!();
+
+    let vocabulary = vocabulary(metas);
+    let fps: Vec<_> = metas
+        .par_iter()
+        .map(|m| measured_fp(m, &vocabulary))
+        .collect();
+
+    println!(
+        "{:>7} {:>10} {:>5} {:>9} {:>9}  path",
+        "words", "filter", "hash", "fp(exp)", "fp(meas)"
+    );
+    for (m, fp) in metas.iter().zip(&fps) {
+        let filter = m.bloom_filter();
+        println!(
+            "{:>7} {:>10} {:>5} {:>9.5} {:>9}  {}",
+            m.words().len(),
+            human_size(filter.num_bytes()),
+            filter.num_hash(),
+            filter.expected_fp(m.words().len()),
+            fp.map(|fp| format!("{fp:.5}")).unwrap_or("-".to_owned()),
+            m.path().display(),
+        );
+    }
+    println!();
+
+    // 偽陽性率の平均 (単語数で重み付けしない)
+    let measured: Vec<_> = fps.iter().flatten().collect();
+    if !measured.is_empty() {
+        let mean = measured.iter().copied().sum::<f64>() / measured.len() as f64;
+        println!(
+            "measured fp: {mean:.5} (mean over {} pages, {} held-out words)",
+            measured.len(),
+            vocabulary.len()
+        );
+    }
+
+    let bloom_size = bloom_filter_js(metas)?.len();
+    let index_files = search_index_files(metas, cfg.tokenizer())?;
+    let index_size: usize = index_files.iter().map(|(_, c)| c.len()).sum();
+    println!("bloom_filter.js: {}", human_size(bloom_size));
+    println!(
+        "search_index/:   {} ({} files)",
+        human_size(index_size),
+        index_files.len()
+    );
+
+    // 索引の大部分を占める記事を報告する
+    let total: usize = metas.iter().map(|m| m.bloom_filter().num_bytes()).sum();
+    let median = metas
+        .iter()
+        .map(|m| m.bloom_filter().num_bytes())
+        .sorted()
+        .nth(metas.len() / 2)
+        .unwrap_or(0);
+    let large: Vec<_> = metas
+        .iter()
+        .filter(|m| {
+            let size = m.bloom_filter().num_bytes();
+            size >= LARGE_PAGE_MIN_BYTES
+                && size > median * LARGE_PAGE_FACTOR
+                && size as f64 >= total as f64 * LARGE_PAGE_SHARE
+        })
+        .collect();
+    if !large.is_empty() {
+        println!();
+        println!("large pages:");
+    }
+    for m in large {
+        let size = m.bloom_filter().num_bytes();
+        println!(
+            "  {} uses {:.1}% of the bloom filters ({} words). \
+             Consider excluding large tables or logs in it from search \
+             with <!-- search:off --> ... <!-- search:on -->, or the whole page with `search: false`.",
+            m.path().display(),
+            size as f64 / total as f64 * 100.0,
+            m.words().len(),
+        );
+    }
+
+    Ok(())
+}
+
+pub fn stats(search: bool, render_draft: bool) -> Result<()> {
+    let cfg = load_config(render_draft)?;
+    let metas = collect_metadatas(&cfg)?;
+
+    let num_private = metas.iter().filter(|m| m.is_private()).count();
+    let num_tags = metas.iter().flat_map(|m| m.tags()).unique().count();
+    let num_words: usize = metas.iter().map(|m| m.words().len()).sum();
+    println!(
+        "pages: {} ({} private), tags: {}, words: {}",
+        metas.len(),
+        num_private,
+        num_tags,
+        num_words
+    );
+
+    if search {
+        println!();
+        print_search_stats(&cfg, &metas)?;
+    }
+
+    Ok(())
+}
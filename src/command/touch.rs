@@ -0,0 +1,127 @@
+use super::build::load_config;
+use crate::util::{PathExt as _, git_modified_files, write_file};
+use anyhow::{Context as _, Result, bail};
+use chrono::{DateTime, SecondsFormat, Utc};
+use chrono_tz::Tz;
+use regex::Regex;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+/// yaml ヘッダの `update` の行 (末尾のコメントを含む)
+static UPDATE_LINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^update:(\s*)([^#]*?)(\s+#.*)?$").unwrap());
+
+/// yaml ヘッダの `update` の値を `now` に書き換えます。
+/// 元の値が日付のみの場合は日付を、時刻付きの場合は時刻付きの日時を書き込みます。
+/// yaml ヘッダに `update` がない場合や、値が変わらない場合は `None` を返します。
+fn rewrite_update(markdown: &str, now: DateTime<Tz>) -> Option<String> {
+    let mut lines = markdown.split_inclusive('\n');
+    let first = lines.next()?;
+    if first.trim_end() != "---" {
+        return None;
+    }
+
+    let mut rewritten = first.to_owned();
+    let mut changed = false;
+    for line in lines.by_ref() {
+        let body = line.trim_end_matches(['\r', '\n']);
+        if body == "---" || body == "..." {
+            rewritten.push_str(line);
+            break;
+        }
+        let Some(caps) = UPDATE_LINE.captures(body) else {
+            rewritten.push_str(line);
+            continue;
+        };
+
+        let value = if caps[2].contains('T') {
+            now.to_rfc3339_opts(SecondsFormat::Secs, true)
+        } else {
+            now.format("%Y-%m-%d").to_string()
+        };
+        if caps[2] == value {
+            return None;
+        }
+        let comment = caps.get(3).map_or("", |m| m.as_str());
+        rewritten.push_str(&format!("update:{}{value}{comment}", &caps[1]));
+        rewritten.push_str(&line[body.len()..]);
+        changed = true;
+    }
+    if !changed {
+        return None;
+    }
+
+    rewritten.extend(lines);
+    Some(rewritten)
+}
+
+/// 記事の yaml ヘッダの `update` を今日の日付に書き換えます。
+/// ファイルを指定しない場合、 git でコミットされていない変更がある記事を対象にします。
+pub fn touch(files: &[PathBuf]) -> Result<()> {
+    // zakki のルートに移動する前に、指定されたパスを解決しておく
+    let files = files
+        .iter()
+        .map(|f| f.canonicalize().with_context(|| f.display().to_string()))
+        .collect::<Result<Vec<_>>>()?;
+
+    let cfg = load_config(false)?;
+    // サイトのルートがシンボリックリンクを含む場合でも比較できるように、正規化したパスを使う
+    let src_dir = cfg
+        .src_dir()
+        .canonicalize()
+        .with_context(|| cfg.src_dir().display().to_string())?;
+    let files = if files.is_empty() {
+        let pwd = std::env::current_dir()?.canonicalize()?;
+        let modified = git_modified_files(cfg.src_dir())?;
+        modified.into_iter().map(|f| pwd.join(f)).collect()
+    } else {
+        files
+    };
+
+    let now = Utc::now().with_timezone(&cfg.timezone());
+    for file in files.iter().filter(|f| f.extension_is("md")) {
+        if !file.starts_with(&src_dir) {
+            bail!("{} is not in the src directory.", file.display());
+        }
+
+        let markdown = std::fs::read_to_string(file)?;
+        if let Some(rewritten) = rewrite_update(&markdown, now) {
+            write_file(file, rewritten)?;
+            println!("touched {}", file.display());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::rewrite_update;
+    use chrono::TimeZone as _;
+    use chrono_tz::Tz;
+
+    #[test]
+    fn test_rewrite_update() {
+        let now = Tz::Asia__Tokyo
+            .with_ymd_and_hms(2024, 8, 15, 21, 30, 0)
+            .unwrap();
+
+        let markdown =
+            "---\ncreate: 2024-05-13\nupdate: 2024-05-13 # 更新日\n---\n\nupdate: 本文\n";
+        assert_eq!(
+            rewrite_update(markdown, now).unwrap(),
+            "---\ncreate: 2024-05-13\nupdate: 2024-08-15 # 更新日\n---\n\nupdate: 本文\n"
+        );
+
+        let markdown = "---\nupdate: 2024-05-13T09:00:00+09:00\r\n---\r\n";
+        assert_eq!(
+            rewrite_update(markdown, now).unwrap(),
+            "---\nupdate: 2024-08-15T21:30:00+09:00\r\n---\r\n"
+        );
+
+        // 変更がない場合と、 update がない場合
+        assert_eq!(rewrite_update("---\nupdate: 2024-08-15\n---\n", now), None);
+        assert_eq!(rewrite_update("---\ncreate: 2024-05-13\n---\n", now), None);
+        assert_eq!(rewrite_update("# update: 2024-05-13\n", now), None);
+    }
+}
@@ -1,19 +1,248 @@
+mod locale;
+mod validate;
+
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
-use crate::util::PathExt as _;
-use anyhow::bail;
-use serde::Deserialize;
+pub use locale::{Locale, UiStrings};
+
+use crate::util::{
+    Dictionary, LinkStyle, PageDate, PathExt as _, Tokenizer, encode_url_path, split_site_url,
+};
+use anyhow::{Context as _, bail};
+use chrono::{NaiveDate, TimeZone as _};
+use chrono_tz::Tz;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
 const fn default_search_fp() -> f64 {
     0.0001f64
 }
 
-#[derive(Deserialize)]
+/// サイト内検索の方式
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchEngine {
+    /// 記事ごとの Bloom filter による検索
+    #[default]
+    Bloom,
+    /// 転置索引を用いた、ランキング付きの全文検索
+    Inverted,
+}
+
+impl SearchEngine {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Bloom => "bloom",
+            Self::Inverted => "inverted",
+        }
+    }
+}
+
+/// 記事の URL の形式
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum UrlStyle {
+    /// `foo.html` に出力し、 `foo.html` でリンクする (file:// でも開ける)
+    #[default]
+    File,
+    /// `foo/index.html` に出力し、 `foo/` でリンクする (Web サーバー向け)
+    Pretty,
+}
+
+/// yaml ヘッダで省略された作成日・更新日の取得元
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum DatesFrom {
+    /// 省略を許さない
+    #[default]
+    Header,
+    /// ソースファイルの git の履歴 (コミットされていない場合は更新日時)
+    Git,
+}
+
+/// `zakki build -d` で下書きを描画する際の設定 (`[preview]`)
+/// 下書きはデフォルトでサイトマップ、フィード、サイト内検索から除外されます。
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct PreviewConfig {
+    /// 下書きを sitemap.xml に含めるか
+    #[serde(default)]
+    pub sitemap: bool,
+
+    /// 下書きを feed.xml に含めるか
+    #[serde(default)]
+    pub feed: bool,
+
+    /// 下書きをサイト内検索の対象にするか
+    #[serde(default)]
+    pub search: bool,
+}
+
+/// 公開日 (`publish`) と公開期限 (`expire`) による記事の選別の設定
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Schedule {
+    /// 判定に使う現在の日付 (`None` の場合は現在の日時)
+    pub now: Option<NaiveDate>,
+    /// 公開日が未来の記事も変換するか
+    pub render_future: bool,
+    /// 公開期限を過ぎた記事も変換するか
+    pub render_expired: bool,
+}
+
+/// 記事のコレクション (`[[collections]]`)
+/// `src/` 下のディレクトリごとに、記事の扱いを設定します。
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Collection {
+    /// コレクションの名前
+    pub name: String,
+
+    /// `src/` からのディレクトリのパス (省略時は名前と同じ)
+    #[serde(default)]
+    dir: Option<PathBuf>,
+
+    /// 記事を暗号化するか
+    #[serde(default)]
+    encrypted: Option<bool>,
+
+    /// 記事一覧、タグ、アーカイブ、ナビゲーション、フィード、サイトマップに載せるか
+    #[serde(default)]
+    listed: Option<bool>,
+
+    /// サイト内検索の対象にするか
+    #[serde(default)]
+    indexed: Option<bool>,
+
+    /// 下書きとして扱うか
+    #[serde(default)]
+    draft: Option<bool>,
+
+    /// 記事のページに使うテンプレート (zakki のルートからのパス)
+    #[serde(default)]
+    template: Option<PathBuf>,
+
+    /// 記事の出力先のパターン (省略時は `zakki.toml` の `permalink`)
+    #[serde(default)]
+    permalink: Option<String>,
+
+    /// 読み込んだテンプレートの内容
+    #[serde(skip)]
+    template_html: Option<String>,
+}
+
+impl Collection {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            dir: None,
+            encrypted: None,
+            listed: None,
+            indexed: None,
+            draft: None,
+            template: None,
+            permalink: None,
+            template_html: None,
+        }
+    }
+
+    /// 組み込みのコレクション (`public/`, `private/`, `draft/`)
+    fn builtins() -> Vec<Self> {
+        vec![
+            Self::new("public"),
+            Self {
+                encrypted: Some(true),
+                ..Self::new("private")
+            },
+            Self {
+                draft: Some(true),
+                ..Self::new("draft")
+            },
+        ]
+    }
+
+    /// 指定のない項目を `base` の値で補います。
+    fn or(self, base: Self) -> Self {
+        Self {
+            name: self.name,
+            dir: self.dir.or(base.dir),
+            encrypted: self.encrypted.or(base.encrypted),
+            listed: self.listed.or(base.listed),
+            indexed: self.indexed.or(base.indexed),
+            draft: self.draft.or(base.draft),
+            template: self.template.or(base.template),
+            permalink: self.permalink.or(base.permalink),
+            template_html: self.template_html.or(base.template_html),
+        }
+    }
+
+    /// 組み込みのコレクションに `collections` を加えます。
+    /// 組み込みのものと同じ名前のコレクションは、指定した項目だけを上書きします。
+    fn merge(collections: Vec<Self>) -> Vec<Self> {
+        let mut merged = Self::builtins();
+        for c in collections {
+            match merged.iter().position(|b| b.name == c.name) {
+                Some(i) => {
+                    let base = merged.remove(i);
+                    merged.push(c.or(base));
+                }
+                None => merged.push(c),
+            }
+        }
+        // 入れ子になったディレクトリでは、より深いものを優先する
+        merged.sort_by_key(|c| std::cmp::Reverse(c.dir().components().count()));
+        merged
+    }
+
+    /// 記事を暗号化するか
+    pub fn encrypted(&self) -> bool {
+        self.encrypted.unwrap_or(false)
+    }
+
+    /// 記事一覧、タグ、アーカイブ、ナビゲーション、フィード、サイトマップに載せるか
+    pub fn listed(&self) -> bool {
+        self.listed.unwrap_or(true)
+    }
+
+    /// サイト内検索の対象にするか
+    pub fn indexed(&self) -> bool {
+        self.indexed.unwrap_or(true)
+    }
+
+    /// 下書きとして扱うか
+    pub fn draft(&self) -> bool {
+        self.draft.unwrap_or(false)
+    }
+
+    /// `src/` からのディレクトリのパス
+    pub fn dir(&self) -> &Path {
+        self.dir.as_deref().unwrap_or(Path::new(&self.name))
+    }
+
+    /// 記事のページに使うテンプレート (指定がない場合は `None`)
+    pub fn template(&self) -> Option<&str> {
+        self.template_html.as_deref()
+    }
+}
+
+/// ユーザー辞書のファイル名 (zakki のルートに置く)
+const DICTIONARY_FILE: &str = "dictionary.txt";
+
+const fn default_index_page_size() -> usize {
+    20
+}
+
+/// `language` を省略した場合の既定の言語
+const DEFAULT_LANGUAGE: &str = "ja";
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct FileConfig {
     /// サイトの名前
     site_name: String,
 
     // サイトの公開先 URL
+    #[serde(default, deserialize_with = "validate::publish_url")]
     publish_url: Option<String>,
 
     /// ページの暗号化に使うパスワード
@@ -27,9 +256,35 @@ pub struct FileConfig {
     /// サイト内検索の偽陽性率
     /// INFO: デフォルト値の即値による指定は現状できない。
     /// see: <https://github.com/serde-rs/serde/issues/368>
-    #[serde(default = "default_search_fp")]
+    #[serde(
+        default = "default_search_fp",
+        deserialize_with = "validate::search_fp"
+    )]
     search_fp: f64,
 
+    /// サイト内検索の方式
+    #[serde(default)]
+    search_engine: SearchEngine,
+
+    /// サイト内検索で分割せずに 1 つの単語として扱う語
+    #[serde(default)]
+    dictionary: Vec<String>,
+
+    /// サイト内検索で英単語のステミングを行うか
+    #[serde(default)]
+    search_stemming: bool,
+
+    /// 記事一覧の 1 ページあたりの記事数
+    #[serde(
+        default = "default_index_page_size",
+        deserialize_with = "validate::index_page_size"
+    )]
+    index_page_size: usize,
+
+    /// 記事間のリンクを可視化するページを生成するか
+    #[serde(default)]
+    link_graph_page: bool,
+
     /// 追加の JS ファイル
     /// インターネット上へのリンクも扱えるよう、 PathBuf ではなく String で扱う
     #[serde(default)]
@@ -39,6 +294,48 @@ pub struct FileConfig {
     /// インターネット上へのリンクも扱えるよう、 PathBuf ではなく String で扱う
     #[serde(default)]
     css_list: Vec<String>,
+
+    /// 下書きを描画する際の設定
+    #[serde(default)]
+    preview: PreviewConfig,
+
+    /// 記事の日付の解釈に使うタイムゾーン (例: Asia/Tokyo)
+    #[serde(default)]
+    timezone: Tz,
+
+    /// yaml ヘッダで省略された作成日・更新日の取得元
+    #[serde(default)]
+    dates_from: DatesFrom,
+
+    /// 記事のコレクション
+    /// 組み込みのコレクションと同じ名前のものは、組み込みのものを置き換えます。
+    #[serde(default, deserialize_with = "validate::collections")]
+    collections: Vec<Collection>,
+
+    /// 記事の出力先のパターン (例: /:year/:slug.html)
+    #[serde(default)]
+    permalink: Option<String>,
+
+    /// 記事の URL の形式
+    #[serde(default)]
+    url_style: UrlStyle,
+
+    /// サイトの既定の言語 (例: ja)
+    /// 省略した場合は `ja` で、 UI の文言は英語になります。
+    #[serde(default)]
+    language: Option<String>,
+
+    /// サイトで使う言語の一覧 (既定の言語は省略可)
+    #[serde(default)]
+    languages: Vec<String>,
+
+    /// 言語ごとの UI の文言 (組み込みの文言を上書きする)
+    #[serde(default)]
+    ui_strings: BTreeMap<String, BTreeMap<String, String>>,
+
+    /// サイトで使う言語 (既定の言語が先頭)
+    #[serde(skip)]
+    locales: Vec<Locale>,
 }
 
 impl FileConfig {
@@ -56,10 +353,100 @@ impl FileConfig {
         let cfg = std::fs::read(cfg)?;
         let cfg = std::str::from_utf8(&cfg)?;
 
-        toml::from_str(cfg).map_err(Into::into)
+        let mut cfg: Self =
+            toml::from_str(cfg).map_err(|e| anyhow::anyhow!(validate::toml_error(cfg, &e)))?;
+
+        // dictionary.txt があれば、1 行 1 語として辞書に加える
+        // 空行と # で始まる行は無視する
+        if std::fs::exists(DICTIONARY_FILE)? {
+            let dict = std::fs::read_to_string(DICTIONARY_FILE)?;
+            let terms = dict
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                .map(str::to_owned);
+            cfg.dictionary.extend(terms);
+        }
+
+        cfg.resolve_locales()?;
+
+        for c in &mut cfg.collections {
+            if c.dir().is_absolute() || c.dir().components().any(|c| c.as_os_str() == "..") {
+                bail!("collection `{}`: dir must be a path under src/.", c.name);
+            }
+            if let Some(template) = &c.template {
+                let html = std::fs::read_to_string(template)
+                    .with_context(|| format!("collection `{}`: {}", c.name, template.display()))?;
+                c.template_html = Some(html);
+            }
+        }
+
+        Ok(cfg)
+    }
+
+    /// `language` と `languages` から、サイトで使う言語を決めます。
+    fn resolve_locales(&mut self) -> anyhow::Result<()> {
+        let default_language = self.language.as_deref().unwrap_or(DEFAULT_LANGUAGE);
+        let mut languages = vec![default_language.to_owned()];
+        languages.extend(self.languages.iter().cloned());
+        let languages: Vec<_> = languages.into_iter().unique().collect();
+        if let Some(lang) = self.ui_strings.keys().find(|l| !languages.contains(l)) {
+            bail!("ui_strings.{lang}: `{lang}` is not in `languages`.");
+        }
+        self.locales = languages
+            .iter()
+            .enumerate()
+            .map(|(i, lang)| {
+                let overrides = self.ui_strings.get(lang);
+                if i == 0 && self.language.is_none() {
+                    Locale::with_strings(lang, true, UiStrings::unspecified(), overrides)
+                } else {
+                    Locale::new(lang, i == 0, overrides)
+                }
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(())
+    }
+
+    /// 既定値を補った実際の設定を TOML 形式で返します (`zakki config --show`)。
+    /// パスワードは伏せて出力します。
+    pub fn effective_toml(mut self) -> anyhow::Result<String> {
+        let footer = default_footer(&self.site_name);
+        self.footer.get_or_insert(footer);
+        if self.password.is_some() {
+            self.password = Some("********".to_owned());
+        }
+        self.collections = Collection::merge(std::mem::take(&mut self.collections))
+            .into_iter()
+            .map(|c| Collection {
+                dir: Some(c.dir().to_owned()),
+                encrypted: Some(c.encrypted()),
+                listed: Some(c.listed()),
+                indexed: Some(c.indexed()),
+                draft: Some(c.draft()),
+                ..c
+            })
+            .collect();
+        self.languages = self.locales.iter().map(|l| l.lang.clone()).collect();
+        self.ui_strings = self
+            .locales
+            .iter()
+            .map(|l| {
+                Ok((
+                    l.lang.clone(),
+                    serde_json::from_value(serde_json::to_value(&l.strings)?)?,
+                ))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(toml::to_string_pretty(&self)?)
     }
 }
 
+/// `footer` を省略した場合の内容
+fn default_footer(site_name: &str) -> String {
+    format!("&copy; {site_name}. All rights reserved.")
+}
+
 pub struct Config {
     /// サイト名
     site_name: String,
@@ -77,12 +464,40 @@ pub struct Config {
     dst_dir: PathBuf,
     /// サイト内検索の偽陽性率
     search_fp: f64,
+    /// サイト内検索の方式
+    search_engine: SearchEngine,
+    /// サイト内検索で分割せずに 1 つの単語として扱う語
+    dictionary: Vec<String>,
+    /// サイト内検索の単語の分割方法 (ユーザー辞書とステミングの設定)
+    tokenizer: Tokenizer,
+    /// 記事一覧の 1 ページあたりの記事数
+    index_page_size: usize,
+    /// 記事間のリンクを可視化するページを生成するか
+    link_graph_page: bool,
     /// 追加の JS ファイル
     /// インターネット上へのリンクも扱えるよう、 PathBuf ではなく String で扱う
     js_list: Vec<String>,
     /// 追加の CSS ファイル
     /// インターネット上へのリンクも扱えるよう、 PathBuf ではなく String で扱う
     css_list: Vec<String>,
+    /// 下書きを描画する際の設定
+    preview: PreviewConfig,
+    /// 公開日と公開期限による記事の選別の設定
+    schedule: Schedule,
+    /// 記事の日付の解釈に使うタイムゾーン
+    timezone: Tz,
+    /// yaml ヘッダで省略された作成日・更新日の取得元
+    dates_from: DatesFrom,
+    /// 記事のコレクション (ディレクトリの深い順)
+    collections: Vec<Collection>,
+    /// どのコレクションにも属さない記事の扱い
+    root_collection: Collection,
+    /// 記事の出力先のパターン
+    permalink: Option<String>,
+    /// リンクに書く URL の形式 (記事の URL の形式とサイトのベースパス)
+    link_style: LinkStyle,
+    /// サイトで使う言語 (既定の言語が先頭)
+    locales: Vec<Locale>,
 }
 
 impl Config {
@@ -92,11 +507,22 @@ impl Config {
         src_dir: PathBuf,
         dst_dir: PathBuf,
     ) -> Self {
+        let base_path = file_config
+            .publish_url
+            .as_ref()
+            .map_or("/".to_owned(), |url| split_site_url(url).1);
+
         Self {
-            footer: file_config.footer.unwrap_or(format!(
-                "&copy; {}. All rights reserved.",
-                &file_config.site_name
-            )),
+            tokenizer: Tokenizer::new(
+                Dictionary::new(&file_config.dictionary),
+                file_config.search_stemming,
+            ),
+            dictionary: file_config.dictionary,
+            link_style: LinkStyle::new(file_config.url_style == UrlStyle::Pretty, base_path),
+            collections: Collection::merge(file_config.collections),
+            footer: file_config
+                .footer
+                .unwrap_or_else(|| default_footer(&file_config.site_name)),
             site_name: file_config.site_name,
             publish_url: file_config.publish_url,
             render_draft,
@@ -104,8 +530,18 @@ impl Config {
             src_dir,
             dst_dir,
             search_fp: file_config.search_fp,
+            search_engine: file_config.search_engine,
+            index_page_size: file_config.index_page_size,
+            link_graph_page: file_config.link_graph_page,
             js_list: file_config.js_list,
             css_list: file_config.css_list,
+            preview: file_config.preview,
+            schedule: Schedule::default(),
+            timezone: file_config.timezone,
+            dates_from: file_config.dates_from,
+            root_collection: Collection::new(""),
+            permalink: file_config.permalink,
+            locales: file_config.locales,
         }
     }
 
@@ -125,6 +561,57 @@ impl Config {
         self.publish_url.as_ref()
     }
 
+    /// 出力先ディレクトリからの相対パスに対応する公開 URL を返します。
+    /// パスは公開先 URL のベースパスからの相対パスとして扱い、パーセントエンコードします。
+    /// pretty URL の場合は、末尾の `index.html` を省きます。
+    /// 公開先 URL が設定されていない場合は `None` を返します。
+    pub fn page_url(&self, build_root_to_dst: impl AsRef<Path>) -> Option<String> {
+        let (origin, base_path) = split_site_url(self.publish_url.as_ref()?);
+        let path = build_root_to_dst.as_ref().to_str().unwrap();
+        let path = self.link_style.href_of(path);
+        let path = path.trim_start_matches("./");
+        Some(format!("{origin}{base_path}{}", encode_url_path(path)))
+    }
+
+    /// リンクに書く URL の形式 (記事の URL の形式とサイトのベースパス)
+    pub fn link_style(&self) -> &LinkStyle {
+        &self.link_style
+    }
+
+    /// サイトで使う言語 (既定の言語が先頭)
+    pub fn locales(&self) -> &[Locale] {
+        &self.locales
+    }
+
+    /// サイトの既定の言語
+    pub fn default_locale(&self) -> &Locale {
+        &self.locales[0]
+    }
+
+    /// 言語コードに対応する言語を返します。
+    pub fn locale_of(&self, lang: &str) -> Option<&Locale> {
+        self.locales.iter().find(|l| l.lang == lang)
+    }
+
+    /// 記事のファイル (ページバンドルの場合はディレクトリ) の `src/` からのパスから、
+    /// 翻訳のグループのキー (言語コードを除いたパス) を返します。
+    pub fn translation_key_of(&self, file: &Path) -> PathBuf {
+        let stem = file.file_stem().unwrap().to_str().unwrap();
+        let (base, _) = self.split_lang_suffix(stem);
+        file.parent().unwrap().join(base)
+    }
+
+    /// ファイル名 (拡張子を除く) の末尾の言語コード (`foo.en` の `en`) を分けます。
+    /// サイトで使う言語のコードでない場合は、言語コードなしとみなします。
+    pub fn split_lang_suffix<'a>(&self, stem: &'a str) -> (&'a str, Option<&'a str>) {
+        match stem.rsplit_once('.') {
+            Some((base, lang)) if !base.is_empty() && self.locale_of(lang).is_some() => {
+                (base, Some(lang))
+            }
+            _ => (stem, None),
+        }
+    }
+
     pub fn footer(&self) -> &str {
         &self.footer
     }
@@ -141,12 +628,126 @@ impl Config {
         self.search_fp
     }
 
+    pub fn search_engine(&self) -> SearchEngine {
+        self.search_engine
+    }
+
+    pub fn dictionary(&self) -> &Vec<String> {
+        &self.dictionary
+    }
+
+    pub fn search_stemming(&self) -> bool {
+        self.tokenizer.stemming()
+    }
+
+    /// サイト内検索の単語の分割方法
+    pub fn tokenizer(&self) -> &Tokenizer {
+        &self.tokenizer
+    }
+
+    pub fn preview(&self) -> &PreviewConfig {
+        &self.preview
+    }
+
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+
+    pub fn set_schedule(&mut self, schedule: Schedule) {
+        self.schedule = schedule;
+    }
+
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+    }
+
+    pub fn dates_from(&self) -> DatesFrom {
+        self.dates_from
+    }
+
+    pub fn url_style(&self) -> UrlStyle {
+        if self.link_style.is_pretty() {
+            UrlStyle::Pretty
+        } else {
+            UrlStyle::File
+        }
+    }
+
+    /// コレクションの記事の出力先のパターンを返します。
+    /// 指定がない場合は `None` を返します。
+    pub fn permalink_of<'a>(&'a self, collection: &'a Collection) -> Option<&'a str> {
+        collection
+            .permalink
+            .as_deref()
+            .or(self.permalink.as_deref())
+    }
+
+    /// 記事が属するコレクションを返します。
+    /// `build_root_to_dst` は出力先ディレクトリ (または `src/`) からの相対パスです。
+    pub fn collection_of(&self, build_root_to_dst: impl AsRef<Path>) -> &Collection {
+        let path = build_root_to_dst.as_ref();
+        self.collections
+            .iter()
+            .find(|c| path.starts_with(c.dir()))
+            .unwrap_or(&self.root_collection)
+    }
+
+    /// 公開日と公開期限の判定に使う現在の日時
+    /// `--now` で日付を指定した場合は、サイトのタイムゾーンでのその日の 0 時です。
+    pub fn now(&self) -> PageDate {
+        match self.schedule.now {
+            Some(date) => {
+                let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+                let datetime = self.timezone.from_local_datetime(&midnight).earliest();
+                PageDate::from_datetime(datetime.unwrap(), self.timezone)
+            }
+            None => PageDate::from_datetime(chrono::Utc::now(), self.timezone),
+        }
+    }
+
+    pub fn index_page_size(&self) -> usize {
+        self.index_page_size
+    }
+
+    pub fn link_graph_page(&self) -> bool {
+        self.link_graph_page
+    }
+
+    /// ソースファイルが属するページバンドル (`index.md` を持つディレクトリ) を返します。
+    /// ページバンドルが入れ子になっている場合は、最も外側のものを返します。
+    /// `src/` 直下とコレクションのディレクトリは、ページバンドルになりません。
+    pub fn bundle_of<'a>(&self, src_path: &'a Path) -> Option<&'a Path> {
+        let rel = src_path.strip_prefix(self.src_dir()).ok()?;
+        let is_collection_dir = |dir: &Path| self.collections.iter().any(|c| c.dir() == dir);
+
+        let dirs: Vec<_> = src_path
+            .ancestors()
+            .skip(1)
+            .zip(rel.ancestors().skip(1))
+            .take_while(|(_, rel)| !rel.as_os_str().is_empty())
+            .collect();
+        dirs.into_iter()
+            .rev()
+            .find(|(dir, rel)| !is_collection_dir(rel) && dir.join("index.md").is_file())
+            .map(|(dir, _)| dir)
+    }
+
+    /// ソースファイルが記事として変換されるか
+    /// ページバンドル内の `index.md` 以外の Markdown は、ページバンドルのファイルとしてそのままコピーします。
+    pub fn is_page(&self, src_path: impl AsRef<Path>) -> bool {
+        let src_path = src_path.as_ref();
+        src_path.extension_is("md")
+            && self
+                .bundle_of(src_path)
+                .is_none_or(|bundle| bundle.join("index.md") == src_path)
+    }
+
     /// ソースファイルの出力先パスを返します。
     pub fn dst_path_of(&self, src_path: impl AsRef<Path>) -> PathBuf {
         let src_path = src_path.as_ref();
         let rel = src_path.strip_prefix(self.src_dir()).unwrap();
 
-        if rel.extension_is("md") {
+        if self.is_page(src_path) {
             self.dst_dir().join(rel.with_extension("html"))
         } else {
             self.dst_dir().join(rel)
@@ -161,3 +762,161 @@ impl Config {
         &self.css_list
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{Config, FileConfig};
+    use std::path::{Path, PathBuf};
+
+    fn config_of(toml: &str) -> Config {
+        config_with_src(toml, PathBuf::from("src"))
+    }
+
+    fn config_with_src(toml: &str, src_dir: PathBuf) -> Config {
+        let mut file_config: FileConfig = toml::from_str(toml).unwrap();
+        file_config.resolve_locales().unwrap();
+        Config::new(file_config, false, src_dir, PathBuf::from("build"))
+    }
+
+    #[test]
+    fn test_collection_of() {
+        let cfg = config_of(
+            r#"
+            site_name = "test"
+
+            # 組み込みのコレクションの上書きは、指定した項目だけを変える
+            [[collections]]
+            name = "private"
+            permalink = "/p/:slug.html"
+
+            [[collections]]
+            name = "notes"
+            listed = false
+
+            [[collections]]
+            name = "secret-notes"
+            dir = "notes/secret"
+            encrypted = true
+            "#,
+        );
+
+        let private = cfg.collection_of("private/a.md");
+        assert_eq!(private.name, "private");
+        assert!(private.encrypted() && private.listed() && !private.draft());
+        assert_eq!(cfg.permalink_of(private), Some("/p/:slug.html"));
+
+        assert!(cfg.collection_of("draft/a.md").draft());
+        let notes = cfg.collection_of("notes/a.md");
+        assert!(!notes.listed() && !notes.encrypted());
+        // 入れ子のディレクトリでは、より深いコレクションを使う
+        let secret = cfg.collection_of("notes/secret/a.md");
+        assert_eq!(secret.name, "secret-notes");
+        assert!(secret.encrypted() && secret.listed());
+        // どのコレクションにも属さない記事
+        assert_eq!(cfg.collection_of("rootpage.md").name, "");
+        assert_eq!(cfg.collection_of("publicity/a.md").name, "");
+
+        let cfg = config_of(
+            r#"
+            site_name = "test"
+            [[collections]]
+            name = "private"
+            encrypted = false
+            "#,
+        );
+        assert!(!cfg.collection_of("private/a.md").encrypted());
+    }
+
+    #[test]
+    fn test_duplicate_collections() {
+        let toml = r#"
+            site_name = "test"
+            [[collections]]
+            name = "notes"
+            [[collections]]
+            name = "notes"
+            listed = false
+            "#;
+        let Err(err) = toml::from_str::<FileConfig>(toml) else {
+            panic!("duplicate collections must be rejected");
+        };
+        assert!(
+            err.message()
+                .contains("collection `notes` is defined more than once")
+        );
+    }
+
+    #[test]
+    fn test_translation_key_of() {
+        let cfg = config_of(
+            r#"
+            site_name = "test"
+            language = "ja"
+            languages = ["en", "zh-TW"]
+            "#,
+        );
+        let key = |path: &str| cfg.translation_key_of(Path::new(path));
+
+        // 言語コードを除いたパスが同じ記事は、同じグループになる
+        assert_eq!(key("public/foo.md"), Path::new("public/foo"));
+        assert_eq!(key("public/foo.en.md"), Path::new("public/foo"));
+        assert_eq!(key("public/foo.zh-TW.md"), Path::new("public/foo"));
+        assert_eq!(key("public/trip.en"), Path::new("public/trip"));
+        // サイトで使わない言語のコードや、ディレクトリの異なる記事は別のグループ
+        assert_eq!(key("public/foo.fr.md"), Path::new("public/foo.fr"));
+        assert_eq!(key("public/v1.2.md"), Path::new("public/v1.2"));
+        assert_eq!(key("notes/foo.en.md"), Path::new("notes/foo"));
+        assert_eq!(key("public/.en.md"), Path::new("public/.en"));
+
+        assert_eq!(cfg.default_locale().strings.newer, "新しい記事");
+        let cfg = config_of(r#"site_name = "test""#);
+        assert_eq!(cfg.default_locale().lang, "ja");
+        assert_eq!(cfg.default_locale().strings.newer, "Newer");
+    }
+
+    #[test]
+    fn test_bundle_of() {
+        let src = std::env::temp_dir()
+            .join(format!("zakki-test-bundle-of-{}", std::process::id()))
+            .join("src");
+        for file in [
+            "index.md",
+            "public/index.md",
+            "public/foo.md",
+            "public/trip/index.md",
+            "public/trip/notes.md",
+            "public/trip/day1/index.md",
+            "public/trip/day1/photo.png",
+            "public/other/a.md",
+        ] {
+            let path = src.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        let cfg = config_with_src(r#"site_name = "test""#, src.clone());
+        let bundle_of = |file: &str| cfg.bundle_of(&src.join(file)).map(Path::to_path_buf);
+        let is_page = |file: &str| cfg.is_page(src.join(file));
+
+        // src/ 直下とコレクションのディレクトリはページバンドルにならない
+        assert_eq!(bundle_of("index.md"), None);
+        assert_eq!(bundle_of("public/index.md"), None);
+        assert_eq!(bundle_of("public/foo.md"), None);
+        assert_eq!(bundle_of("public/other/a.md"), None);
+        assert!(is_page("index.md") && is_page("public/index.md") && is_page("public/foo.md"));
+        assert!(is_page("public/other/a.md"));
+
+        // 入れ子のページバンドルは、最も外側のものに属する
+        let trip = Some(src.join("public/trip"));
+        assert_eq!(bundle_of("public/trip/index.md"), trip);
+        assert_eq!(bundle_of("public/trip/notes.md"), trip);
+        assert_eq!(bundle_of("public/trip/day1/index.md"), trip);
+        assert_eq!(bundle_of("public/trip/day1/photo.png"), trip);
+        assert!(is_page("public/trip/index.md"));
+        // ページバンドル内の index.md 以外の Markdown は記事にしない
+        assert!(!is_page("public/trip/notes.md"));
+        assert!(!is_page("public/trip/day1/index.md"));
+        assert!(!is_page("public/trip/day1/photo.png"));
+
+        std::fs::remove_dir_all(src.parent().unwrap()).unwrap();
+    }
+}
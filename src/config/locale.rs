@@ -0,0 +1,211 @@
+use anyhow::{Context as _, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// テンプレートなどで使う UI の文言
+/// 文言はプレーンテキストとして扱い、 HTML に埋め込む際にエスケープします。
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct UiStrings {
+    /// 言語の名前 (言語の切り替えに使う)
+    pub language_name: String,
+    /// 検索欄のプレースホルダ
+    pub search_placeholder: String,
+    /// 暗号化されたページの見出し
+    pub protected: String,
+    /// パスワード欄のプレースホルダ
+    pub password_placeholder: String,
+    /// 復号するボタン
+    pub decrypt: String,
+    /// 作成日
+    pub created: String,
+    /// 最終更新日
+    pub updated: String,
+    /// 記事一覧の新しい記事へのリンク
+    pub newer: String,
+    /// 記事一覧の古い記事へのリンク
+    pub older: String,
+    /// 関連記事の見出し
+    pub related: String,
+    /// バックリンクの見出し
+    pub backlinks: String,
+    /// 変更履歴
+    pub history: String,
+    /// アーカイブ
+    pub archive: String,
+    /// リンクグラフ
+    pub link_graph: String,
+    /// 他の言語の記事へのリンクの見出し
+    pub translations: String,
+    /// 下書きのバナー
+    pub draft: String,
+}
+
+impl UiStrings {
+    fn ja() -> Self {
+        Self {
+            language_name: "日本語".to_owned(),
+            search_placeholder: "サイト内を検索...".to_owned(),
+            protected: "このページは保護されています。".to_owned(),
+            password_placeholder: "パスワードを入力".to_owned(),
+            decrypt: "復号".to_owned(),
+            created: "作成日".to_owned(),
+            updated: "更新日".to_owned(),
+            newer: "新しい記事".to_owned(),
+            older: "古い記事".to_owned(),
+            related: "関連記事".to_owned(),
+            backlinks: "この記事へのリンク".to_owned(),
+            history: "変更履歴".to_owned(),
+            archive: "アーカイブ".to_owned(),
+            link_graph: "リンクグラフ".to_owned(),
+            translations: "他の言語".to_owned(),
+            draft: "下書き".to_owned(),
+        }
+    }
+
+    fn en() -> Self {
+        Self {
+            language_name: "English".to_owned(),
+            search_placeholder: "Input to search...".to_owned(),
+            protected: "This page is protected.".to_owned(),
+            password_placeholder: "Enter your secret key".to_owned(),
+            decrypt: "Decode".to_owned(),
+            created: "Created".to_owned(),
+            updated: "Updated".to_owned(),
+            newer: "Newer".to_owned(),
+            older: "Older".to_owned(),
+            related: "Related articles".to_owned(),
+            backlinks: "Linked from".to_owned(),
+            history: "History".to_owned(),
+            archive: "Archive".to_owned(),
+            link_graph: "Link graph".to_owned(),
+            translations: "Other languages".to_owned(),
+            draft: "DRAFT".to_owned(),
+        }
+    }
+
+    /// 組み込みの文言を返します。
+    /// 組み込みの文言がない言語は英語の文言を使い、言語の名前は言語コードにします。
+    pub fn builtin(lang: &str) -> Self {
+        match lang.split('-').next().unwrap() {
+            "ja" => Self::ja(),
+            "en" => Self::en(),
+            _ => Self {
+                language_name: lang.to_owned(),
+                ..Self::en()
+            },
+        }
+    }
+
+    /// `language` を省略したサイトの既定の言語の文言
+    /// 言語は `ja` ですが、言語を選べなかった頃と同じ英語の文言を使います。
+    pub fn unspecified() -> Self {
+        Self {
+            language_name: "日本語".to_owned(),
+            ..Self::en()
+        }
+    }
+
+    /// 文言を `overrides` で上書きします。
+    /// 存在しないキーを指定した場合はエラーになります。
+    fn overridden(self, lang: &str, overrides: Option<&BTreeMap<String, String>>) -> Result<Self> {
+        let mut strings = serde_json::to_value(self)?;
+        for (key, value) in overrides.into_iter().flatten() {
+            strings[key] = value.clone().into();
+        }
+        serde_json::from_value(strings).with_context(|| format!("ui_strings.{lang}"))
+    }
+}
+
+/// サイトで使う言語
+pub struct Locale {
+    /// 言語コード (例: ja, en)
+    pub lang: String,
+    /// 記事一覧、フィード、検索の索引を出力するディレクトリ (既定の言語の場合は空)
+    pub dir: PathBuf,
+    /// UI の文言
+    pub strings: UiStrings,
+}
+
+impl Locale {
+    /// `is_default` が真の場合は、記事一覧などをルートに出力します。
+    pub fn new(
+        lang: &str,
+        is_default: bool,
+        overrides: Option<&BTreeMap<String, String>>,
+    ) -> Result<Self> {
+        Self::with_strings(lang, is_default, UiStrings::builtin(lang), overrides)
+    }
+
+    /// 組み込みの文言の代わりに `builtin` を `overrides` で上書きした文言を使います。
+    pub fn with_strings(
+        lang: &str,
+        is_default: bool,
+        builtin: UiStrings,
+        overrides: Option<&BTreeMap<String, String>>,
+    ) -> Result<Self> {
+        let is_valid =
+            !lang.is_empty() && lang.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+        if !is_valid {
+            bail!("Invalid language code: `{lang}`");
+        }
+
+        Ok(Self {
+            lang: lang.to_owned(),
+            dir: if is_default {
+                PathBuf::new()
+            } else {
+                PathBuf::from(lang)
+            },
+            strings: builtin.overridden(lang, overrides)?,
+        })
+    }
+
+    /// ルートへの相対パス `path_to_root` から、言語のディレクトリへの相対パスを返します。
+    pub fn root_from(&self, path_to_root: &Path) -> PathBuf {
+        if self.dir.as_os_str().is_empty() {
+            path_to_root.to_owned()
+        } else {
+            path_to_root.join(&self.dir)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Locale, UiStrings};
+    use std::collections::BTreeMap;
+    use std::path::Path;
+
+    #[test]
+    fn test_ui_strings() {
+        let overrides = BTreeMap::from([("newer".to_owned(), "Plus récent".to_owned())]);
+        let strings = Locale::new("fr", false, Some(&overrides)).unwrap().strings;
+        assert_eq!(strings.newer, "Plus récent");
+        assert_eq!(strings.older, "Older");
+        assert_eq!(strings.language_name, "fr");
+
+        let overrides = BTreeMap::from([("unknown".to_owned(), "?".to_owned())]);
+        assert!(Locale::new("ja", true, Some(&overrides)).is_err());
+
+        // `language` を省略したサイトは、言語が ja でも英語の文言を使う
+        let locale = Locale::with_strings("ja", true, UiStrings::unspecified(), None).unwrap();
+        assert_eq!(locale.lang, "ja");
+        assert_eq!(locale.strings.search_placeholder, "Input to search...");
+        assert_eq!(locale.strings.newer, "Newer");
+        assert_eq!(
+            Locale::new("ja", true, None).unwrap().strings.newer,
+            "新しい記事"
+        );
+    }
+
+    #[test]
+    fn test_locale_root() {
+        let ja = Locale::new("ja", true, None).unwrap();
+        assert_eq!(ja.root_from(Path::new("..")), Path::new(".."));
+        let en = Locale::new("en", false, None).unwrap();
+        assert_eq!(en.root_from(Path::new("..")), Path::new("../en"));
+        assert!(Locale::new("../en", false, None).is_err());
+    }
+}
@@ -0,0 +1,133 @@
+use serde::{Deserialize, Deserializer, de::Error as _};
+
+use super::Collection;
+
+/// 2 つの文字列の編集距離を返します。
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { diagonal } else { diagonal + 1 };
+            diagonal = row[j + 1];
+            row[j + 1] = cost.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// `candidates` のうち `word` に最も近いものを返します。
+/// 十分に近いものがない場合は `None` を返します。
+fn closest<'a>(word: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let limit = (word.chars().count() / 3).max(2);
+    candidates
+        .iter()
+        .map(|c| (levenshtein(word, c), *c))
+        .filter(|(d, _)| *d <= limit)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, c)| c)
+}
+
+/// serde のエラーメッセージ中の `` `...` `` で囲まれた語を返します。
+fn quoted(message: &str) -> Vec<&str> {
+    message.split('`').skip(1).step_by(2).collect()
+}
+
+/// 未知のキーや値のエラーメッセージに、最も近い有効なキーや値の提案を加えます。
+fn suggest(message: &str) -> Option<String> {
+    if !message.starts_with("unknown field") && !message.starts_with("unknown variant") {
+        return None;
+    }
+    let words = quoted(message);
+    let (word, candidates) = words.split_first()?;
+    closest(word, candidates).map(|c| format!(" (did you mean `{c}`?)"))
+}
+
+/// zakki.toml の読み込みのエラーを、行と列の番号を付けたメッセージにします。
+pub fn toml_error(source: &str, err: &toml::de::Error) -> String {
+    let message = err.message().trim_end();
+    let suggestion = suggest(message).unwrap_or_default();
+    let Some(span) = err.span() else {
+        return format!("zakki.toml: {message}{suggestion}");
+    };
+    let before = &source[..span.start.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap().chars().count() + 1;
+    format!("zakki.toml:{line}:{column}: {message}{suggestion}")
+}
+
+/// サイト内検索の偽陽性率 (0 より大きく 1 より小さい)
+pub fn search_fp<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let fp = f64::deserialize(deserializer)?;
+    if !(fp > 0.0 && fp < 1.0) {
+        return Err(D::Error::custom(format!(
+            "search_fp must be greater than 0 and less than 1, but got {fp}"
+        )));
+    }
+    Ok(fp)
+}
+
+/// 記事一覧の 1 ページあたりの記事数 (1 以上)
+pub fn index_page_size<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+    let size = usize::deserialize(deserializer)?;
+    if size == 0 {
+        return Err(D::Error::custom("index_page_size must be at least 1"));
+    }
+    Ok(size)
+}
+
+/// 記事のコレクション (名前が重複しない)
+pub fn collections<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Collection>, D::Error> {
+    let collections = Vec::<Collection>::deserialize(deserializer)?;
+    for (i, c) in collections.iter().enumerate() {
+        if collections[..i].iter().any(|prev| prev.name == c.name) {
+            return Err(D::Error::custom(format!(
+                "collection `{}` is defined more than once",
+                c.name
+            )));
+        }
+    }
+    Ok(collections)
+}
+
+/// サイトの公開先 URL (`http://` または `https://` で始まる絶対 URL)
+pub fn publish_url<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    let url = String::deserialize(deserializer)?;
+    let host = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .map(|rest| rest.split(['/', '?', '#']).next().unwrap());
+    let is_valid = host.is_some_and(|h| !h.is_empty()) && !url.contains(char::is_whitespace);
+    if !is_valid {
+        return Err(D::Error::custom(format!(
+            "publish_url must be an absolute URL starting with http:// or https://, but got `{url}`"
+        )));
+    }
+    Ok(Some(url))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{closest, levenshtein, suggest};
+
+    #[test]
+    fn test_closest() {
+        assert_eq!(levenshtein("css_lsit", "css_list"), 2);
+        assert_eq!(levenshtein("", "abc"), 3);
+        let keys = ["css_list", "js_list", "search_fp"];
+        assert_eq!(closest("css_lsit", &keys), Some("css_list"));
+        assert_eq!(closest("searchfp", &keys), Some("search_fp"));
+        assert_eq!(closest("unrelated", &keys), None);
+
+        let message = "unknown field `css_lsit`, expected one of `site_name`, `css_list`";
+        assert_eq!(
+            suggest(message).as_deref(),
+            Some(" (did you mean `css_list`?)")
+        );
+        assert_eq!(suggest("invalid type: string, expected a float"), None);
+    }
+}
@@ -1,16 +1,30 @@
 mod aes_256_cbc;
 mod bloom_filter;
+mod date;
 mod file_io;
 mod fxhash;
+mod git;
+mod html_escape;
+mod normalize;
 mod path_ext;
+mod search_index;
+mod search_query;
 mod segmenter;
+mod url;
 mod vec_ext;
 
 pub use aes_256_cbc::*;
 pub use bloom_filter::*;
+pub use date::*;
 pub use file_io::*;
+pub use git::*;
+pub use html_escape::*;
+pub use normalize::*;
 pub use path_ext::*;
+pub use search_index::*;
+pub use search_query::*;
 pub use segmenter::*;
+pub use url::*;
 pub use vec_ext::*;
 
 /// 本プロジェクトの asset ディレクトリ下にあるファイルの内容を読み込みます
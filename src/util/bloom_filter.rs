@@ -21,7 +21,8 @@ impl BloomFilter {
     /// * `num_words` - フィルターに格納する単語の数
     /// * `fp` - 誤り許容率
     pub fn new(num_words: usize, fp: f64) -> Self {
-        let num_words = num_words as f64;
+        // 単語がない場合も、どの単語も含まないフィルターになるようにする
+        let num_words = num_words.max(1) as f64;
         let num_bit = -num_words * fp.ln() / 2.0f64.ln().powi(2);
         let num_byte = (num_bit / 8.0).ceil() as u32;
         let num_hash = (num_bit * 2.0f64.ln() / num_words).ceil() as u8;
@@ -31,6 +32,23 @@ impl BloomFilter {
         }
     }
 
+    /// フィルターのバイト数
+    pub fn num_bytes(&self) -> usize {
+        self.filter.len()
+    }
+
+    /// 使用するハッシュ関数の数
+    pub fn num_hash(&self) -> u8 {
+        self.num_hash
+    }
+
+    /// `num_words` 個の単語を格納したときの、理論上の偽陽性率を返します。
+    pub fn expected_fp(&self, num_words: usize) -> f64 {
+        let num_bit = (self.filter.len() * 8) as f64;
+        let k = self.num_hash as f64;
+        (1.0 - (-k * num_words as f64 / num_bit).exp()).powf(k)
+    }
+
     pub fn insert_word(&mut self, word: &str) {
         let num_bit = self.filter.len() as u32 * 8;
         let hashes = fxhash32_multi(word)
@@ -42,7 +60,6 @@ impl BloomFilter {
         });
     }
 
-    #[cfg(test)]
     pub fn contains(&self, word: &str) -> bool {
         let num_bit = (self.filter.len() as u32) * 8;
         let mut hashes = fxhash32_multi(word)
@@ -0,0 +1,121 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Datelike as _, FixedOffset, NaiveDate, SecondsFormat, TimeZone};
+use chrono_tz::Tz;
+use serde::{Serialize, Serializer};
+
+/// 記事の日付
+/// `yyyy-MM-dd` (日付のみ) または RFC 3339 (時刻とタイムゾーン付き) で指定します。
+/// 日付のみの場合は、サイトのタイムゾーンでのその日の 0 時として扱います。
+/// 比較は時刻を含めて行い、表示にはサイトのタイムゾーンでの日付を使います。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PageDate {
+    /// サイトのタイムゾーンでの日時
+    datetime: DateTime<FixedOffset>,
+}
+
+impl PageDate {
+    /// 日付を解析します。
+    pub fn parse(s: &str, tz: Tz) -> Result<Self> {
+        let s = s.trim();
+        let error = || {
+            anyhow!(
+                "Invalid date `{s}`: expected YYYY-MM-DD or RFC 3339 (e.g. 2024-05-13T09:30:00+09:00)"
+            )
+        };
+
+        let datetime = if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            // parse_from_str は桁数の足りない値 (2024-5-3) も受け付けるため、書式を確認する
+            if date.format("%Y-%m-%d").to_string() != s {
+                return Err(error());
+            }
+            tz.from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+                .earliest()
+                .ok_or_else(error)?
+                .fixed_offset()
+        } else {
+            DateTime::parse_from_rfc3339(s)
+                .map_err(|_| error())?
+                .with_timezone(&tz)
+                .fixed_offset()
+        };
+
+        Ok(Self { datetime })
+    }
+
+    /// 日時から作成します。
+    pub fn from_datetime<T: TimeZone>(datetime: DateTime<T>, tz: Tz) -> Self {
+        Self {
+            datetime: datetime.with_timezone(&tz).fixed_offset(),
+        }
+    }
+
+    /// サイトのタイムゾーンでの日付
+    pub fn date(&self) -> NaiveDate {
+        self.datetime.date_naive()
+    }
+
+    pub fn year(&self) -> i32 {
+        self.datetime.year()
+    }
+
+    pub fn month(&self) -> u32 {
+        self.datetime.month()
+    }
+
+    pub fn day(&self) -> u32 {
+        self.datetime.day()
+    }
+
+    /// RFC 3339 形式の文字列 (フィードやサイトマップ用)
+    pub fn to_rfc3339(self) -> String {
+        self.datetime.to_rfc3339_opts(SecondsFormat::Secs, true)
+    }
+}
+
+/// `yyyy-MM-dd` 形式で表示します。
+impl std::fmt::Display for PageDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.date().format("%Y-%m-%d"))
+    }
+}
+
+/// metadata.js には `yyyy-MM-dd` 形式で書き出します。
+/// クライアントは日付を文字列として比較・表示します。
+impl Serialize for PageDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PageDate;
+    use chrono_tz::Tz;
+
+    #[test]
+    fn test_parse() {
+        let date = PageDate::parse("2024-05-13", Tz::UTC).unwrap();
+        assert_eq!(date.to_string(), "2024-05-13");
+        assert_eq!(date.to_rfc3339(), "2024-05-13T00:00:00Z");
+
+        let date = PageDate::parse("2024-05-13", Tz::Asia__Tokyo).unwrap();
+        assert_eq!(date.to_rfc3339(), "2024-05-13T00:00:00+09:00");
+
+        // サイトのタイムゾーンでの日付で表示する
+        let date = PageDate::parse("2024-05-13T20:00:00Z", Tz::Asia__Tokyo).unwrap();
+        assert_eq!(date.to_string(), "2024-05-14");
+        assert_eq!(date.to_rfc3339(), "2024-05-14T05:00:00+09:00");
+
+        for invalid in ["2024-5-3", "2024-02-30", "2024/05/13", "today", ""] {
+            assert!(PageDate::parse(invalid, Tz::UTC).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_order() {
+        let a = PageDate::parse("2024-05-13", Tz::Asia__Tokyo).unwrap();
+        let b = PageDate::parse("2024-05-13T09:00:00+09:00", Tz::UTC).unwrap();
+        let c = PageDate::parse("2024-05-13T01:00:00Z", Tz::UTC).unwrap();
+        assert!(a < b && b < c);
+    }
+}
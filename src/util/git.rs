@@ -0,0 +1,137 @@
+use anyhow::{Result, bail};
+use chrono::{DateTime, FixedOffset};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// ディレクトリ `dir` で git コマンドを実行し、標準出力を返します。
+/// git がインストールされていない場合や、 git リポジトリの外で実行した場合は `None` を返します。
+fn git(dir: &Path, args: &[&str]) -> Result<Option<String>> {
+    // 日本語などを含むパスをエスケープさせない
+    // エラーメッセージで判定するため、メッセージを翻訳させない
+    let output = match Command::new("git")
+        .current_dir(dir)
+        .env("LC_ALL", "C")
+        .args(["-c", "core.quotepath=false"])
+        .args(args)
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("not a git repository") {
+            return Ok(None);
+        }
+        bail!("git {} failed: {}", args.join(" "), stderr.trim());
+    }
+    Ok(Some(String::from_utf8(output.stdout)?))
+}
+
+/// ファイルのパスを、 git を実行するディレクトリと、そこからのファイル名に分けます。
+fn split_file_path(path: &Path) -> (&Path, String) {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = path.file_name().unwrap_or(path.as_os_str());
+    (dir, name.to_string_lossy().into_owned())
+}
+
+/// ファイルの最初のコミットと最後のコミットの日時を返します。
+/// コミットされていないファイルの場合は `None` を返します。
+pub fn git_commit_dates(
+    path: impl AsRef<Path>,
+) -> Result<Option<(DateTime<FixedOffset>, DateTime<FixedOffset>)>> {
+    let (dir, name) = split_file_path(path.as_ref());
+    let Some(log) = git(dir, &["log", "--follow", "--format=%aI", "--", &name])? else {
+        return Ok(None);
+    };
+
+    // 新しいコミットから順に出力される
+    let dates = log
+        .lines()
+        .map(DateTime::parse_from_rfc3339)
+        .collect::<Result<Vec<_>, _>>()?;
+    match (dates.last(), dates.first()) {
+        (Some(first), Some(last)) => Ok(Some((*first, *last))),
+        _ => Ok(None),
+    }
+}
+
+/// ファイルの変更履歴の 1 件
+pub struct Revision {
+    /// コミットのハッシュ
+    pub hash: String,
+    /// コミットの日時 (author date)
+    pub date: DateTime<FixedOffset>,
+    /// コミットメッセージの 1 行目
+    pub subject: String,
+    /// 直前のコミットからの差分 (unified diff)
+    pub diff: String,
+}
+
+/// ファイルの変更履歴を、新しいコミットから順に返します。
+/// git リポジトリの外のファイルや、コミットされていないファイルの場合は空の列を返します。
+pub fn git_revisions(path: impl AsRef<Path>) -> Result<Vec<Revision>> {
+    let (dir, name) = split_file_path(path.as_ref());
+    // 各コミットは NUL で始まり、ヘッダの各項目は US (0x1f) で区切られる
+    let format = "--format=%x00%H%x1f%aI%x1f%s";
+    let args = ["log", "--follow", "-p", "--no-color", format, "--", &name];
+    let Some(log) = git(dir, &args)? else {
+        return Ok(vec![]);
+    };
+
+    log.split('\0')
+        .filter(|c| !c.is_empty())
+        .map(|commit| {
+            let (header, diff) = commit.split_once('\n').unwrap_or((commit, ""));
+            let mut fields = header.splitn(3, '\x1f');
+            let (Some(hash), Some(date), Some(subject)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                bail!("Unexpected output of git log: {header}");
+            };
+            Ok(Revision {
+                hash: hash.to_owned(),
+                date: DateTime::parse_from_rfc3339(date)?,
+                subject: subject.to_owned(),
+                diff: diff.trim_matches('\n').to_owned(),
+            })
+        })
+        .collect()
+}
+
+/// ファイルに、コミットされていない変更があるか
+/// git リポジトリの外のファイルは変更がないものとします。
+pub fn git_is_modified(path: impl AsRef<Path>) -> Result<bool> {
+    let (dir, name) = split_file_path(path.as_ref());
+    let status = git(dir, &["status", "--porcelain", "--", &name])?;
+    Ok(status.is_some_and(|s| !s.trim().is_empty()))
+}
+
+/// ディレクトリ下の、コミットされていない変更があるファイル (追跡されていないファイルを含む) を返します。
+/// パスはカレントディレクトリからの相対パスです。
+pub fn git_modified_files(dir: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+    let dir = dir.as_ref().to_string_lossy();
+    let args = ["status", "--porcelain", "--untracked-files=all", "--", &dir];
+    let Some(status) = git(Path::new("."), &args)? else {
+        return Ok(vec![]);
+    };
+    let Some(root) = git(Path::new("."), &["rev-parse", "--show-toplevel"])? else {
+        return Ok(vec![]);
+    };
+    let root = PathBuf::from(root.trim());
+    let pwd = std::env::current_dir()?.canonicalize()?;
+
+    // 各行は "XY path" または "XY from -> to" の形式で、パスはリポジトリのルートからの相対パス
+    let files = status
+        .lines()
+        .filter(|l| !l.starts_with(" D") && !l.starts_with("D "))
+        .filter_map(|l| l.get(3..))
+        .map(|p| p.rsplit(" -> ").next().unwrap().trim_matches('"'))
+        .filter_map(|p| root.join(p).strip_prefix(&pwd).ok().map(Path::to_path_buf))
+        .collect();
+    Ok(files)
+}
@@ -0,0 +1,14 @@
+/// HTML の特殊文字をエスケープします。
+/// 属性値の中でも使えるよう、引用符もエスケープします。
+pub fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    s.chars().for_each(|c| match c {
+        '&' => out.push_str("&amp;"),
+        '<' => out.push_str("&lt;"),
+        '>' => out.push_str("&gt;"),
+        '"' => out.push_str("&quot;"),
+        '\'' => out.push_str("&#39;"),
+        _ => out.push(c),
+    });
+    out
+}
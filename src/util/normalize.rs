@@ -0,0 +1,112 @@
+use std::borrow::Cow;
+use unicode_normalization::UnicodeNormalization as _;
+
+/// 検索用に文字列を正規化します。
+/// NFKC により全角英数字や半角カナなどの幅を揃え、小文字に統一します。
+/// クライアント (search_query.js) の正規化と一致させる必要があります。
+pub fn normalize(text: &str) -> String {
+    text.nfkc().collect::<String>().to_lowercase()
+}
+
+/// 英単語の複数形を単数形に揃えます (Harman の S-stemmer)。
+/// 4 文字未満の単語と、英小文字以外を含む単語はそのまま返します。
+pub fn stem(word: &str) -> Cow<'_, str> {
+    if word.len() < 4 || !word.bytes().all(|b| b.is_ascii_lowercase()) {
+        return Cow::Borrowed(word);
+    }
+
+    let ends_with_any = |suffixes: &[&str]| suffixes.iter().any(|s| word.ends_with(s));
+    if let Some(base) = word.strip_suffix("ies") {
+        if ends_with_any(&["eies", "aies"]) {
+            Cow::Borrowed(word)
+        } else {
+            Cow::Owned(format!("{base}y"))
+        }
+    } else if word.ends_with("es") {
+        if ends_with_any(&["aes", "ees", "oes"]) {
+            Cow::Borrowed(word)
+        } else {
+            Cow::Borrowed(&word[..word.len() - 1])
+        }
+    } else if let Some(base) = word.strip_suffix('s') {
+        if ends_with_any(&["us", "ss"]) {
+            Cow::Borrowed(word)
+        } else {
+            Cow::Borrowed(base)
+        }
+    } else {
+        Cow::Borrowed(word)
+    }
+}
+
+/// 分かち書きのモデルが対応しておらず、 bigram に分割する文字か
+/// ハングル、注音符号と、モデルの漢字の範囲 (`一` から `龠` まで) 外の漢字が該当します。
+/// クライアント (search_query.js) と一致させる必要があります。
+pub fn is_bigram_char(c: char) -> bool {
+    matches!(c,
+        // ハングル字母
+        '\u{1100}'..='\u{11FF}'
+        // ハングル互換字母
+        | '\u{3130}'..='\u{318F}'
+        // ハングル音節
+        | '\u{AC00}'..='\u{D7AF}'
+        // 注音符号
+        | '\u{3100}'..='\u{312F}'
+        | '\u{31A0}'..='\u{31BF}'
+        // CJK 統合漢字拡張 A
+        | '\u{3400}'..='\u{4DBF}'
+        // CJK 統合漢字のうち、 `龠` より後のもの
+        | '\u{9FA1}'..='\u{9FFF}'
+        // CJK 統合漢字拡張 B 以降
+        | '\u{20000}'..='\u{3FFFF}'
+    )
+}
+
+/// 文字列を重なりのある 2 文字ずつに分割します。
+/// 1 文字の場合はそのまま返します。
+pub fn bigrams(text: &str) -> Vec<&str> {
+    let indices: Vec<_> = text
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain([text.len()])
+        .collect();
+    if indices.len() <= 2 {
+        return vec![text];
+    }
+    indices.windows(3).map(|w| &text[w[0]..w[2]]).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{bigrams, normalize, stem};
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("ＡＢＣ１２３"), "abc123");
+        assert_eq!(normalize("ｶﾀｶﾅ ｶﾞ"), "カタカナ ガ");
+        assert_eq!(normalize("Rust"), "rust");
+    }
+
+    #[test]
+    fn test_stem() {
+        let cases = [
+            ("caches", "cache"),
+            ("queries", "query"),
+            ("pages", "page"),
+            ("words", "word"),
+            ("status", "status"),
+            ("class", "class"),
+            ("has", "has"),
+            ("日本語s", "日本語s"),
+        ];
+        for (word, expected) in cases {
+            assert_eq!(stem(word), expected, "{word}");
+        }
+    }
+
+    #[test]
+    fn test_bigrams() {
+        assert_eq!(bigrams("한국어"), vec!["한국", "국어"]);
+        assert_eq!(bigrams("한"), vec!["한"]);
+    }
+}
@@ -1,4 +1,4 @@
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 pub trait PathExt {
     /// 拡張子が ext かどうかを確認します。
@@ -13,6 +13,14 @@ pub trait PathExt {
 
     /// ディレクトリ直下に file_name のファイルを持つか確かめます
     fn has_file(&self, file_name: &str) -> std::io::Result<bool>;
+
+    /// `.` と `..` を字句的に取り除いたパスを返します。
+    /// 起点より上を指す `..` は残します。
+    fn normalized(&self) -> PathBuf;
+
+    /// ディレクトリ `base` から self への相対パスを返します。
+    /// self と base は、同じ起点からの正規化されたパスであることを仮定しています。
+    fn relative_from(&self, base: &Path) -> PathBuf;
 }
 
 impl PathExt for Path {
@@ -52,4 +60,71 @@ impl PathExt for Path {
 
         Ok(has_file)
     }
+
+    fn normalized(&self) -> PathBuf {
+        let mut out = PathBuf::new();
+        for c in self.components() {
+            match c {
+                Component::CurDir => {}
+                // 起点より上を指す `..` は取り除かずに残す
+                Component::ParentDir => match out.components().next_back() {
+                    Some(Component::Normal(_)) => {
+                        out.pop();
+                    }
+                    _ => out.push(".."),
+                },
+                c => out.push(c),
+            }
+        }
+        out
+    }
+
+    fn relative_from(&self, base: &Path) -> PathBuf {
+        let common = self
+            .components()
+            .zip(base.components())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        let mut out = PathBuf::new();
+        base.components().skip(common).for_each(|_| out.push(".."));
+        self.components().skip(common).for_each(|c| out.push(c));
+        if out.as_os_str().is_empty() {
+            out.push(".");
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PathExt as _;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_normalized() {
+        let p = Path::new("public/./foo/../bar.html").normalized();
+        assert_eq!(p, PathBuf::from("public/bar.html"));
+
+        let p = Path::new("public/../../bar.html").normalized();
+        assert_eq!(p, PathBuf::from("../bar.html"));
+
+        let p = Path::new("../../x").normalized();
+        assert_eq!(p, PathBuf::from("../../x"));
+
+        let p = Path::new("a/../../../x").normalized();
+        assert_eq!(p, PathBuf::from("../../x"));
+    }
+
+    #[test]
+    fn test_relative_from() {
+        let p = Path::new("public/foo/img.png").relative_from(Path::new("2024"));
+        assert_eq!(p, PathBuf::from("../public/foo/img.png"));
+
+        let p = Path::new("public/bar.html").relative_from(Path::new("public"));
+        assert_eq!(p, PathBuf::from("bar.html"));
+
+        let p = Path::new("index.html").relative_from(Path::new(""));
+        assert_eq!(p, PathBuf::from("index.html"));
+    }
 }
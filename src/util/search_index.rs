@@ -0,0 +1,345 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+use super::Dictionary;
+use super::fxhash::fxhash64;
+
+/// BM25 のパラメータ k1
+const BM25_K1: f64 = 1.2;
+/// BM25 のパラメータ b
+const BM25_B: f64 = 0.75;
+/// タイトルに含まれる単語の重み (本文での出現回数に換算)
+const TITLE_WEIGHT: f64 = 5.0;
+/// タグに含まれる単語の重み (本文での出現回数に換算)
+const TAG_WEIGHT: f64 = 3.0;
+/// 前方一致した単語のスコアの倍率
+const PREFIX_WEIGHT: f64 = 0.5;
+/// 1 つのシャードに格納する単語数の目安
+const TERMS_PER_SHARD: usize = 2000;
+/// シャード数の上限
+const MAX_SHARDS: usize = 64;
+/// 本文の 1 つのシャードに格納する文書数
+pub const DOCS_PER_TEXT_SHARD: usize = 50;
+
+/// 単語がタイトルに含まれることを表すフラグ
+const FIELD_TITLE: u32 = 1;
+/// 単語がタグに含まれることを表すフラグ
+const FIELD_TAG: u32 = 2;
+
+/// 検索用の単語の分割方法 (ユーザー辞書と英単語のステミングの設定)
+/// クライアント (search_query.js) の分割方法と一致させる必要があります。
+#[derive(Default)]
+pub struct Tokenizer {
+    dictionary: Dictionary,
+    stemming: bool,
+}
+
+impl Tokenizer {
+    pub fn new(dictionary: Dictionary, stemming: bool) -> Self {
+        Self {
+            dictionary,
+            stemming,
+        }
+    }
+
+    /// 英単語のステミングを行うか
+    pub fn stemming(&self) -> bool {
+        self.stemming
+    }
+
+    /// テキストを検索用の単語列に分割します。
+    /// テキストを正規化した上で、分かち書きのモデルが対応していない文字 (ハングルなど) の並びは bigram に、
+    /// それ以外は辞書とモデルで単語に分割します。
+    pub fn tokenize(&self, text: &str) -> Vec<String> {
+        let text = super::normalize(text);
+
+        // bigram に分割する文字の並びと、それ以外の並びに分ける
+        let mut runs = vec![];
+        let mut start = 0;
+        let mut prev = None;
+        for (i, c) in text.char_indices() {
+            let bigram = super::is_bigram_char(c);
+            if prev.is_some_and(|p| p != bigram) {
+                runs.push((&text[start..i], !bigram));
+                start = i;
+            }
+            prev = Some(bigram);
+        }
+        if let Some(bigram) = prev {
+            runs.push((&text[start..], bigram));
+        }
+
+        let mut tokens = vec![];
+        for (run, bigram) in runs {
+            if bigram {
+                tokens.extend(super::bigrams(run).into_iter().map(str::to_owned));
+                continue;
+            }
+            let words = super::segment_with(run, &self.dictionary);
+            // スペースのみの場合は無視する
+            let words = words.iter().map(|w| w.trim()).filter(|w| !w.is_empty());
+            if self.stemming {
+                tokens.extend(words.map(|w| super::stem(w).into_owned()));
+            } else {
+                tokens.extend(words.map(str::to_owned));
+            }
+        }
+        tokens
+    }
+}
+
+/// 単語が格納されるシャードの番号を返します。
+/// 前方一致検索で読み込むシャードを 1 つに絞れるよう、先頭の文字のハッシュで決めます。
+fn shard_of(term: &str, num_shards: usize) -> usize {
+    let first = term.chars().next().map(String::from).unwrap_or_default();
+    (fxhash64(&first) % num_shards as u64) as usize
+}
+
+/// 索引に登録する文書
+pub struct SearchDocument<'a> {
+    pub title: &'a str,
+    pub tags: &'a [String],
+    pub text: &'a str,
+}
+
+struct Posting {
+    /// 文書番号
+    doc: u32,
+    /// 本文での出現回数
+    tf: u32,
+    /// 本文以外で単語が含まれる場所 (FIELD_* の論理和)
+    fields: u32,
+}
+
+impl Posting {
+    /// フィールドの重みを加味した出現回数
+    fn weighted_tf(&self) -> f64 {
+        let mut tf = self.tf as f64;
+        if self.fields & FIELD_TITLE != 0 {
+            tf += TITLE_WEIGHT;
+        }
+        if self.fields & FIELD_TAG != 0 {
+            tf += TAG_WEIGHT;
+        }
+        tf
+    }
+}
+
+/// 索引全体に関する情報
+/// クライアントはまずこれを読み込み、必要なシャードだけを後から読み込みます。
+#[derive(Serialize)]
+pub struct SearchIndexMeta {
+    num_shards: usize,
+    /// 各文書の本文の単語数
+    doc_lens: Vec<u32>,
+    avg_doc_len: f64,
+    k1: f64,
+    b: f64,
+    title_weight: f64,
+    tag_weight: f64,
+    prefix_weight: f64,
+    docs_per_text_shard: usize,
+}
+
+/// 全文検索用の転置索引
+pub struct SearchIndex {
+    doc_lens: Vec<u32>,
+    postings: BTreeMap<String, Vec<Posting>>,
+}
+
+impl SearchIndex {
+    /// 文書の列から索引を作成します。
+    /// 文書番号は与えた順に 0 から振られます。
+    pub fn new<'a>(
+        docs: impl IntoIterator<Item = SearchDocument<'a>>,
+        tokenizer: &Tokenizer,
+    ) -> Self {
+        let mut doc_lens = Vec::new();
+        let mut postings: BTreeMap<String, Vec<Posting>> = BTreeMap::new();
+
+        for (doc, d) in docs.into_iter().enumerate() {
+            let doc = doc as u32;
+            let mut terms: HashMap<String, (u32, u32)> = HashMap::new();
+
+            let body = tokenizer.tokenize(d.text);
+            doc_lens.push(body.len() as u32);
+            body.into_iter()
+                .for_each(|t| terms.entry(t).or_default().0 += 1);
+            tokenizer
+                .tokenize(d.title)
+                .into_iter()
+                .for_each(|t| terms.entry(t).or_default().1 |= FIELD_TITLE);
+            d.tags
+                .iter()
+                .flat_map(|tag| {
+                    // タグ全体でも、タグを分割した単語でも一致するようにする
+                    // タグ全体もクエリと同じく正規化・ステミングする
+                    let mut ts = tokenizer.tokenize(tag);
+                    let tag = super::normalize(tag);
+                    ts.push(if tokenizer.stemming() {
+                        super::stem(&tag).into_owned()
+                    } else {
+                        tag
+                    });
+                    ts
+                })
+                .for_each(|t| terms.entry(t).or_default().1 |= FIELD_TAG);
+
+            terms.into_iter().for_each(|(term, (tf, fields))| {
+                postings
+                    .entry(term)
+                    .or_default()
+                    .push(Posting { doc, tf, fields });
+            });
+        }
+
+        Self { doc_lens, postings }
+    }
+
+    fn num_shards(&self) -> usize {
+        (self.postings.len() / TERMS_PER_SHARD).clamp(1, MAX_SHARDS)
+    }
+
+    fn avg_doc_len(&self) -> f64 {
+        if self.doc_lens.is_empty() {
+            return 0.0;
+        }
+        self.doc_lens.iter().sum::<u32>() as f64 / self.doc_lens.len() as f64
+    }
+
+    pub fn meta(&self) -> SearchIndexMeta {
+        SearchIndexMeta {
+            num_shards: self.num_shards(),
+            doc_lens: self.doc_lens.clone(),
+            avg_doc_len: self.avg_doc_len(),
+            k1: BM25_K1,
+            b: BM25_B,
+            title_weight: TITLE_WEIGHT,
+            tag_weight: TAG_WEIGHT,
+            prefix_weight: PREFIX_WEIGHT,
+            docs_per_text_shard: DOCS_PER_TEXT_SHARD,
+        }
+    }
+
+    /// 索引をシャードに分割します。
+    /// 各シャードは単語から posting list への写像で、 posting list は
+    /// `[文書番号の差分, 出現回数, フィールド, ...]` の形に平坦化して圧縮します。
+    pub fn shards(&self) -> Vec<BTreeMap<&str, Vec<u32>>> {
+        let num_shards = self.num_shards();
+        let mut shards = vec![BTreeMap::new(); num_shards];
+        self.postings.iter().for_each(|(term, postings)| {
+            let mut prev = 0;
+            let list: Vec<u32> = postings
+                .iter()
+                .flat_map(|p| {
+                    let delta = p.doc - prev;
+                    prev = p.doc;
+                    [delta, p.tf, p.fields]
+                })
+                .collect();
+            shards[shard_of(term, num_shards)].insert(&term[..], list);
+        });
+        shards
+    }
+
+    /// BM25 による 1 つの posting のスコア
+    fn score(&self, df: usize, posting: &Posting) -> f64 {
+        let n = self.doc_lens.len() as f64;
+        let df = df as f64;
+        let idf = (1.0 + (n - df + 0.5) / (df + 0.5)).ln();
+        let tf = posting.weighted_tf();
+        let len = self.doc_lens[posting.doc as usize] as f64;
+        let norm = 1.0 - BM25_B + BM25_B * len / self.avg_doc_len().max(1.0);
+        idf * tf * (BM25_K1 + 1.0) / (tf + BM25_K1 * norm)
+    }
+
+    /// 単語の各文書に対するスコアを、文書番号から (一致した単語, スコア) への写像で返します。
+    /// 各文書について、前方一致する単語のうち最もスコアの高いものを採用します。
+    pub fn word_scores(&self, word: &str) -> HashMap<usize, (&str, f64)> {
+        let mut best: HashMap<usize, (&str, f64)> = HashMap::new();
+        self.postings
+            .range(word.to_owned()..)
+            .take_while(|(term, _)| term.starts_with(word))
+            .for_each(|(term, postings)| {
+                let weight = if term == word { 1.0 } else { PREFIX_WEIGHT };
+                postings.iter().for_each(|p| {
+                    let s = weight * self.score(postings.len(), p);
+                    let e = best.entry(p.doc as usize).or_insert((term, s));
+                    if e.1 < s {
+                        *e = (term, s);
+                    }
+                });
+            });
+        best
+    }
+
+    /// 文書が単語 (完全一致) を含むかを返します。
+    pub fn contains(&self, term: &str, doc: usize) -> bool {
+        self.postings
+            .get(term)
+            .is_some_and(|ps| ps.iter().any(|p| p.doc as usize == doc))
+    }
+
+    /// 索引を検索し、 (文書番号, スコア) をスコアの高い順に返します。
+    /// `words` は重複のない単語の列です。
+    /// クライアント (script.js) の検索と同じ方法でスコアを計算します。
+    pub fn search(&self, words: &[String]) -> Vec<(usize, f64)> {
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        words.iter().for_each(|w| {
+            self.word_scores(w)
+                .into_iter()
+                .for_each(|(doc, (_, s))| *scores.entry(doc).or_default() += s);
+        });
+
+        let mut scores: Vec<_> = scores.into_iter().collect();
+        scores.sort_by(|(a, x), (b, y)| y.total_cmp(x).then(a.cmp(b)));
+        scores
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SearchDocument, SearchIndex, Tokenizer};
+
+    #[test]
+    fn test_search() {
+        let tags = ["rust".to_owned(), "ＡＰＩ".to_owned()];
+        let docs = [
+            SearchDocument {
+                title: "天気",
+                tags: &[],
+                text: "今日はいい天気です。",
+            },
+            SearchDocument {
+                title: "プログラミング",
+                tags: &tags,
+                text: "プログラミングは楽しい。天気とは関係ない。",
+            },
+            SearchDocument {
+                title: "雑記",
+                tags: &[],
+                text: "特に書くことはない。",
+            },
+        ];
+        let tokenizer = Tokenizer::default();
+        let index = SearchIndex::new(docs, &tokenizer);
+
+        let docs = |q| {
+            index
+                .search(&tokenizer.tokenize(q))
+                .into_iter()
+                .map(|(d, _)| d)
+                .collect::<Vec<_>>()
+        };
+
+        // タイトルに含まれる文書が上位に来る
+        assert_eq!(docs("天気"), vec![0, 1]);
+        // タグでも検索できる
+        assert_eq!(docs("Rust"), vec![1]);
+        // タグもクエリと同じく正規化される
+        assert_eq!(docs("api"), vec![1]);
+        // 前方一致
+        assert_eq!(docs("プログラ"), vec![1]);
+        assert!(docs("xyz").is_empty());
+    }
+}
@@ -0,0 +1,339 @@
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::iter::Peekable;
+use std::str::Chars;
+
+use super::{Tokenizer, normalize};
+
+/// 検索クエリを構成する条件の種類
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum TermKind {
+    /// 単語 (`word`)
+    Word,
+    /// 完全一致するフレーズ (`"exact phrase"`)
+    Phrase,
+    /// タグ (`tag:rust`)
+    Tag,
+    /// 作成日の下限 (`after:2024-01-01`)
+    After,
+    /// 記事が置かれているディレクトリ (`section:public`)
+    Section,
+}
+
+/// 検索クエリを構成する条件
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct QueryTerm {
+    pub kind: TermKind,
+    /// 条件の値 (小文字に統一済み)
+    pub value: String,
+    /// `-` によって否定されているか
+    pub negated: bool,
+}
+
+/// 検索対象の記事
+pub struct QueryDocument<'a> {
+    pub tags: &'a [String],
+    /// 作成日 (yyyy-MM-dd)
+    pub create: &'a str,
+    /// ルートからの相対パス
+    pub path: &'a str,
+    /// 本文のテキスト (ない場合、フレーズは単語の集合として扱う)
+    pub text: Option<&'a str>,
+    /// 本文に単語が含まれるか
+    pub has_word: &'a dyn Fn(&str) -> bool,
+}
+
+impl QueryTerm {
+    /// 記事が条件を満たすかを返します。
+    /// 否定されていない単語はランキングにのみ使うため、常に条件を満たすものとします。
+    pub fn matches(&self, doc: &QueryDocument, tokenizer: &Tokenizer) -> bool {
+        let has_all_words = |s: &str| tokenizer.tokenize(s).iter().all(|w| (doc.has_word)(w));
+        let matched = match self.kind {
+            TermKind::Word if !self.negated => return true,
+            TermKind::Word => has_all_words(&self.value),
+            TermKind::Phrase => match doc.text {
+                Some(text) => normalize(text).contains(&self.value),
+                None => has_all_words(&self.value),
+            },
+            TermKind::Tag => doc.tags.iter().any(|t| normalize(t) == self.value),
+            TermKind::After => doc.create >= &self.value[..],
+            TermKind::Section => normalize(doc.path).starts_with(&format!("{}/", self.value)),
+        };
+        matched != self.negated
+    }
+}
+
+/// `after:` の値 (`YYYY`, `YYYY-MM`, `YYYY-MM-DD`) を、作成日と文字列で比較できるよう 0 埋めします。
+/// 月と日は 1 桁でも構いません。日付として解釈できない場合は `None` を返します。
+fn after_date(value: &str) -> Option<String> {
+    let is_digits =
+        |s: &str, len| (1..=len).contains(&s.len()) && s.bytes().all(|b| b.is_ascii_digit());
+    let parts: Vec<_> = value.split('-').collect();
+    if parts.len() > 3 || parts[0].len() != 4 || !is_digits(parts[0], 4) {
+        return None;
+    }
+    let mut date = parts[0].to_owned();
+    for (part, max) in parts[1..].iter().zip([12, 31]) {
+        if !is_digits(part, 2) {
+            return None;
+        }
+        let n: u32 = part.parse().ok()?;
+        if !(1..=max).contains(&n) {
+            return None;
+        }
+        date += &format!("-{n:02}");
+    }
+    Some(date)
+}
+
+/// 解析済みの検索クエリ
+/// クライアント (search_query.js) と同じ構文・意味を持ちます。
+/// 仕様は asset/search_query_spec.json のテストケースで定めます。
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct SearchQuery {
+    terms: Vec<QueryTerm>,
+}
+
+impl SearchQuery {
+    /// 検索クエリを解析します。
+    /// 解釈できない条件 (例: `foo:bar`, `after:yesterday`, `after:2024-13`) は単語として扱います。
+    /// クエリは索引と同じ方法で正規化してから解析します。
+    pub fn parse(query: &str) -> Self {
+        // 閉じ引用符 (または末尾) までを読む
+        fn read_quoted(chars: &mut Peekable<Chars>) -> String {
+            chars.next();
+            let s: String = chars.by_ref().take_while(|&c| c != '"').collect();
+            s.split_whitespace().join(" ")
+        }
+
+        let mut terms = Vec::new();
+        let query = normalize(query);
+        let mut chars = query.chars().peekable();
+
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            if chars.peek().is_none() {
+                break;
+            }
+
+            let negated = chars.next_if_eq(&'-').is_some();
+
+            let mut head = String::new();
+            while let Some(c) = chars.next_if(|&c| !c.is_whitespace() && c != '"') {
+                head.push(c);
+            }
+
+            let (kind, value) = match head.split_once(':') {
+                Some(("tag", "")) if chars.peek() == Some(&'"') => {
+                    (TermKind::Tag, read_quoted(&mut chars))
+                }
+                Some(("section", "")) if chars.peek() == Some(&'"') => {
+                    (TermKind::Section, read_quoted(&mut chars))
+                }
+                Some(("tag", v)) if !v.is_empty() => (TermKind::Tag, v.to_owned()),
+                Some(("section", v)) if !v.trim_matches('/').is_empty() => {
+                    (TermKind::Section, v.trim_matches('/').to_owned())
+                }
+                Some(("after", v)) if after_date(v).is_some() => {
+                    (TermKind::After, after_date(v).unwrap())
+                }
+                _ if head.is_empty() && chars.peek() == Some(&'"') => {
+                    (TermKind::Phrase, read_quoted(&mut chars))
+                }
+                _ => (TermKind::Word, head),
+            };
+
+            if value.is_empty() {
+                continue;
+            }
+            terms.push(QueryTerm {
+                kind,
+                value,
+                negated,
+            });
+        }
+
+        Self { terms }
+    }
+
+    pub fn terms(&self) -> &[QueryTerm] {
+        &self.terms
+    }
+
+    /// ランキングに使う単語 (否定されていない単語とフレーズに含まれる単語) を返します。
+    pub fn ranking_words(&self, tokenizer: &Tokenizer) -> Vec<String> {
+        self.terms
+            .iter()
+            .filter(|t| !t.negated && matches!(t.kind, TermKind::Word | TermKind::Phrase))
+            .flat_map(|t| tokenizer.tokenize(&t.value))
+            .collect()
+    }
+
+    /// 記事がすべての条件を満たすかを返します。
+    pub fn matches(&self, doc: &QueryDocument, tokenizer: &Tokenizer) -> bool {
+        self.terms.iter().all(|t| t.matches(doc, tokenizer))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{QueryDocument, QueryTerm, SearchQuery};
+    use crate::include_asset;
+    use crate::util::{Dictionary, Tokenizer};
+    use serde::Deserialize;
+    use std::collections::HashSet;
+
+    #[derive(Deserialize)]
+    struct Document {
+        tags: Vec<String>,
+        create: String,
+        path: String,
+        text: String,
+    }
+
+    #[derive(Deserialize)]
+    struct MatchCase {
+        query: String,
+        matches: bool,
+    }
+
+    #[derive(Deserialize)]
+    struct ParseCase {
+        query: String,
+        terms: Vec<QueryTerm>,
+    }
+
+    #[derive(Deserialize)]
+    struct RankingCase {
+        query: String,
+        words: Vec<String>,
+    }
+
+    #[derive(Deserialize)]
+    struct TokenizeCase {
+        text: String,
+        stemming: bool,
+        /// ユーザー辞書の語
+        #[serde(default)]
+        dictionary: Vec<String>,
+        tokens: Vec<String>,
+    }
+
+    #[derive(Deserialize)]
+    struct Spec {
+        parse: Vec<ParseCase>,
+        ranking: Vec<RankingCase>,
+        document: Document,
+        r#match: Vec<MatchCase>,
+        tokenize: Vec<TokenizeCase>,
+    }
+
+    fn spec() -> Spec {
+        serde_json::from_str(include_asset!("search_query_spec.json")).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        for case in spec().parse {
+            let query = SearchQuery::parse(&case.query);
+            assert_eq!(query.terms(), &case.terms[..], "query: {}", case.query);
+        }
+    }
+
+    #[test]
+    fn test_ranking_words() {
+        for case in spec().ranking {
+            let query = SearchQuery::parse(&case.query);
+            let words = query.ranking_words(&Tokenizer::default());
+            assert_eq!(words, case.words, "query: {}", case.query);
+        }
+    }
+
+    #[test]
+    fn test_match() {
+        let spec = spec();
+        let d = &spec.document;
+        let tokenizer = Tokenizer::default();
+        let words: HashSet<_> = tokenizer.tokenize(&d.text).into_iter().collect();
+        let has_word = |w: &str| words.contains(w);
+        let doc = QueryDocument {
+            tags: &d.tags,
+            create: &d.create,
+            path: &d.path,
+            text: Some(&d.text),
+            has_word: &has_word,
+        };
+
+        for case in spec.r#match {
+            let query = SearchQuery::parse(&case.query);
+            let matched = query.matches(&doc, &tokenizer);
+            assert_eq!(matched, case.matches, "query: {}", case.query);
+        }
+    }
+
+    #[test]
+    fn test_tokenize() {
+        for case in spec().tokenize {
+            let tokenizer = Tokenizer::new(Dictionary::new(&case.dictionary), case.stemming);
+            let tokens = tokenizer.tokenize(&case.text);
+            assert_eq!(tokens, case.tokens, "text: {}", case.text);
+        }
+    }
+
+    const CLIENT_TEST: &str = r#"
+const doc = spec.document;
+const words = new Set(tokenize(doc.text));
+const target = { ...doc, hasWord: (w) => words.has(w) };
+for (const c of spec.parse) {
+  const actual = JSON.stringify(parseQuery(c.query));
+  const expected = JSON.stringify(c.terms);
+  if (actual !== expected) throw new Error(`${c.query}: ${actual} != ${expected}`);
+}
+for (const c of spec.ranking) {
+  const actual = JSON.stringify(rankingWords(parseQuery(c.query)));
+  const expected = JSON.stringify(c.words);
+  if (actual !== expected) throw new Error(`${c.query}: ${actual} != ${expected}`);
+}
+for (const c of spec.match) {
+  const actual = matchesQuery(parseQuery(c.query), target);
+  if (actual !== c.matches) throw new Error(`${c.query}: ${actual} != ${c.matches}`);
+}
+for (const c of spec.tokenize) {
+  setStemming(c.stemming);
+  setDictionary(c.dictionary ?? []);
+  const actual = JSON.stringify(tokenize(c.text));
+  const expected = JSON.stringify(c.tokens);
+  if (actual !== expected) throw new Error(`${c.text}: ${actual} != ${expected}`);
+}
+"#;
+
+    /// クライアントの実装 (search_query.js) も同じ仕様を満たすことを確認します。
+    /// node がない環境では何もしません。
+    #[test]
+    fn test_client() {
+        let script = [
+            include_asset!("segmenter.js"),
+            include_asset!("search_query.js"),
+            "const spec = ",
+            include_asset!("search_query_spec.json"),
+            CLIENT_TEST,
+        ]
+        .join("\n");
+
+        let output = match std::process::Command::new("node")
+            .args(["-e", &script])
+            .output()
+        {
+            Ok(output) => output,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+            Err(e) => panic!("{e}"),
+        };
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
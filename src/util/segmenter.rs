@@ -1,12 +1,12 @@
-use std::sync::OnceLock;
+use std::collections::HashMap;
+
+use super::normalize;
 
-use itertools::Itertools;
 use phf::phf_map;
-use regex::Regex;
 
 static BIAS: isize = -332;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 enum Ctype {
     /// 漢数字
     M,
@@ -28,47 +28,25 @@ enum Ctype {
     U,
 }
 
-fn ctype_(str: &str) -> Ctype {
-    static M: OnceLock<Regex> = OnceLock::new();
-    static H: OnceLock<Regex> = OnceLock::new();
-    static I: OnceLock<Regex> = OnceLock::new();
-    static K: OnceLock<Regex> = OnceLock::new();
-    static A: OnceLock<Regex> = OnceLock::new();
-    static N: OnceLock<Regex> = OnceLock::new();
-
-    let m = M.get_or_init(|| Regex::new("[一二三四五六七八九十百千万億兆]").unwrap());
-    let h = H.get_or_init(|| Regex::new("[一-龠々〆ヵヶ]").unwrap());
-    let i = I.get_or_init(|| Regex::new("[ぁ-ん]").unwrap());
-    let k = K.get_or_init(|| Regex::new("[ァ-ヴーｱ-ﾝﾞｰ]").unwrap());
-    let a = A.get_or_init(|| Regex::new("[a-zA-Zａ-ｚＡ-Ｚ]").unwrap());
-    let n = N.get_or_init(|| Regex::new("[0-9０-９]").unwrap());
-
-    if m.is_match(str) {
-        return Ctype::M;
-    }
-    if h.is_match(str) {
-        return Ctype::H;
-    }
-    if i.is_match(str) {
-        return Ctype::I;
-    }
-    if k.is_match(str) {
-        return Ctype::K;
+/// 文字の種類を返します。
+fn ctype_(c: char) -> Ctype {
+    match c {
+        '一' | '二' | '三' | '四' | '五' | '六' | '七' | '八' | '九' | '十' | '百' | '千'
+        | '万' | '億' | '兆' => Ctype::M,
+        '一'..='龠' | '々' | '〆' | 'ヵ' | 'ヶ' => Ctype::H,
+        'ぁ'..='ん' => Ctype::I,
+        'ァ'..='ヴ' | 'ー' | 'ｱ'..='ﾝ' | 'ﾞ' | 'ｰ' => Ctype::K,
+        'a'..='z' | 'A'..='Z' | 'ａ'..='ｚ' | 'Ａ'..='Ｚ' => Ctype::A,
+        '0'..='9' | '０'..='９' => Ctype::N,
+        _ => Ctype::O,
     }
-    if a.is_match(str) {
-        return Ctype::A;
-    }
-    if n.is_match(str) {
-        return Ctype::N;
-    }
-    Ctype::O
 }
 
 fn bc1(a: Ctype, b: Ctype) -> isize {
     match (a, b) {
         (Ctype::H, Ctype::H) => 6,
         (Ctype::I, Ctype::I) => 2461,
-        (Ctype::K, Ctype::H) => 206,
+        (Ctype::K, Ctype::H) => 406,
         (Ctype::O, Ctype::H) => -1378,
         _ => 0,
     }
@@ -435,7 +413,7 @@ fn up2(a: Ctype) -> isize {
 }
 fn up3(a: Ctype) -> isize {
     match a {
-        Ctype::B => -189,
+        Ctype::B => 189,
         _ => 0,
     }
 }
@@ -472,7 +450,7 @@ fn uq3(a: Ctype, b: Ctype) -> isize {
         (Ctype::B, Ctype::N) => 6427,
         (Ctype::B, Ctype::O) => 14761,
         (Ctype::O, Ctype::I) => -827,
-        (Ctype::O, Ctype::N) => -3213,
+        (Ctype::O, Ctype::N) => -3212,
         _ => 0,
     }
 }
@@ -493,127 +471,414 @@ static UW4: phf::Map<&'static str, isize> = phf_map! {"," =>3930,"." =>3508,"―
 static UW5: phf::Map<&'static str, isize> = phf_map! {"," =>465,"." =>-299,"1" =>-514,"E2" =>-32768,"]" =>-2762,"、" =>465,"。" =>-299,"「" =>363,"あ" =>1655,"い" =>331,"う" =>-503,"え" =>1199,"お" =>527,"か" =>647,"が" =>-421,"き" =>1624,"ぎ" =>1971,"く" =>312,"げ" =>-983,"さ" =>-1537,"し" =>-1371,"す" =>-852,"だ" =>-1186,"ち" =>1093,"っ" =>52,"つ" =>921,"て" =>-18,"で" =>-850,"と" =>-127,"ど" =>1682,"な" =>-787,"に" =>-1224,"の" =>-635,"は" =>-578,"べ" =>1001,"み" =>502,"め" =>865,"ゃ" =>3350,"ょ" =>854,"り" =>-208,"る" =>429,"れ" =>504,"わ" =>419,"を" =>-1264,"ん" =>327,"イ" =>241,"ル" =>451,"ン" =>-343,"中" =>-871,"京" =>722,"会" =>-1153,"党" =>-654,"務" =>3519,"区" =>-901,"告" =>848,"員" =>2104,"大" =>-1296,"学" =>-548,"定" =>1785,"嵐" =>-1304,"市" =>-2991,"席" =>921,"年" =>1763,"思" =>872,"所" =>-814,"挙" =>1618,"新" =>-1682,"日" =>218,"月" =>-4353,"査" =>932,"格" =>1356,"機" =>-1508,"氏" =>-1347,"田" =>240,"町" =>-3912,"的" =>-3149,"相" =>1319,"省" =>-1052,"県" =>-4003,"研" =>-997,"社" =>-278,"空" =>-813,"統" =>1955,"者" =>-2233,"表" =>663,"語" =>-1073,"議" =>1219,"選" =>-1018,"郎" =>-368,"長" =>786,"間" =>1191,"題" =>2368,"館" =>-689,"１" =>-514,"Ｅ２" =>-32768,"｢" =>363,"ｲ" =>241,"ﾙ" =>451,"ﾝ" =>-343};
 static UW6: phf::Map<&'static str, isize> = phf_map! {"," =>227,"." =>808,"1" =>-270,"E1" =>306,"、" =>227,"。" =>808,"あ" =>-307,"う" =>189,"か" =>241,"が" =>-73,"く" =>-121,"こ" =>-200,"じ" =>1782,"す" =>383,"た" =>-428,"っ" =>573,"て" =>-1014,"で" =>101,"と" =>-105,"な" =>-253,"に" =>-149,"の" =>-417,"は" =>-236,"も" =>-206,"り" =>187,"る" =>-135,"を" =>195,"ル" =>-673,"ン" =>-496,"一" =>-277,"中" =>201,"件" =>-800,"会" =>624,"前" =>302,"区" =>1792,"員" =>-1212,"委" =>798,"学" =>-960,"市" =>887,"広" =>-695,"後" =>535,"業" =>-697,"相" =>753,"社" =>-507,"福" =>974,"空" =>-822,"者" =>1811,"連" =>463,"郎" =>1082,"１" =>-270,"Ｅ１" =>306,"ﾙ" =>-673,"ﾝ" =>-496};
 
+/// `w4` の直前で単語を区切るかのスコアを返します。正の場合に区切ります。
+/// * `p` - 直前 3 つの位置で区切ったか (`B`: 区切った, `O`: 区切らなかった, `U`: 不明)
+/// * `c` - 前後 6 文字 (`w1` から `w6`) の文字の種類
+/// * `w` - `w(a, b)` が `w{a+1}` から `w{b}` までを連結した文字列を返す関数
+fn score<S: AsRef<str>>(p: [Ctype; 3], c: [Ctype; 6], w: impl Fn(usize, usize) -> S) -> isize {
+    let [p1, p2, p3] = p;
+    let [c1, c2, c3, c4, c5, c6] = c;
+    let get = |table: &phf::Map<&'static str, isize>, a, b| -> isize {
+        *table.get(w(a, b).as_ref()).unwrap_or(&0)
+    };
+
+    let mut score = BIAS;
+
+    score += up1(p1);
+    score += up2(p2);
+    score += up3(p3);
+
+    score += bp1(p1, p2);
+    score += bp2(p2, p3);
+
+    score += get(&UW1, 0, 1);
+    score += get(&UW2, 1, 2);
+    score += get(&UW3, 2, 3);
+    score += get(&UW4, 3, 4);
+    score += get(&UW5, 4, 5);
+    score += get(&UW6, 5, 6);
+
+    score += get(&BW1, 1, 3);
+    score += get(&BW2, 2, 4);
+    score += get(&BW3, 3, 5);
+
+    score += get(&TW1, 0, 3);
+    score += get(&TW2, 1, 4);
+    score += get(&TW3, 2, 5);
+    score += get(&TW4, 3, 6);
+
+    score += uc1(c1);
+    score += uc2(c2);
+    score += uc3(c3);
+    score += uc4(c4);
+    score += uc5(c5);
+    score += uc6(c6);
+
+    score += bc1(c2, c3);
+    score += bc2(c3, c4);
+    score += bc3(c4, c5);
+
+    score += tc1(c1, c2, c3);
+    score += tc2(c2, c3, c4);
+    score += tc3(c3, c4, c5);
+    score += tc4(c4, c5, c6);
+
+    score += uq1(p1, c1);
+    score += uq2(p2, c2);
+    score += uq3(p3, c3);
+
+    score += bq1(p2, c2, c3);
+    score += bq2(p2, c3, c4);
+    score += bq3(p3, c2, c3);
+    score += bq4(p3, c3, c4);
+
+    score += tq1(p2, c1, c2, c3);
+    score += tq2(p2, c2, c3, c4);
+    score += tq3(p3, c1, c2, c3);
+    score += tq4(p3, c2, c3, c4);
+
+    score
+}
+
+/// 前後の番兵 (`B3B2B1`, `E1E2E3`) の長さ
+const SENTINEL_LEN: usize = 6;
+
+/// TinySegmenter のモデルで文字列を単語に分割します。
 pub fn segment(input: &str) -> Vec<&str> {
     let mut result = vec![];
+    if input.is_empty() {
+        return result;
+    }
+
+    // 前後に番兵を付けた文字列
+    // 連続する文字の連結が部分文字列として得られるため、照合のたびに文字列を作らずに済む
+    let padded = format!("B3B2B1{input}E1E2E3");
+    // 各文字 (番兵を含む) の padded 上の開始位置と、末尾の位置
+    let len = input.len() + SENTINEL_LEN;
+    let bounds: Vec<_> = [0, 2, 4]
+        .into_iter()
+        .chain(input.char_indices().map(|(i, _)| i + SENTINEL_LEN))
+        .chain([len, len + 2, len + 4, len + 6])
+        .collect();
+    let ctypes: Vec<_> = [Ctype::O; 3]
+        .into_iter()
+        .chain(input.chars().map(ctype_))
+        .chain([Ctype::O; 3])
+        .collect();
+
+    let mut p = [Ctype::U; 3];
+    let mut word_start = SENTINEL_LEN;
+    for i in 4..(ctypes.len() - 3) {
+        let c = std::array::from_fn(|k| ctypes[i - 3 + k]);
+        let w = |a: usize, b: usize| &padded[bounds[i - 3 + a]..bounds[i - 3 + b]];
+
+        let mut next = Ctype::O;
+        if score(p, c, w) > 0 {
+            result.push(&input[word_start - SENTINEL_LEN..bounds[i] - SENTINEL_LEN]);
+            word_start = bounds[i];
+            next = Ctype::B;
+        }
+        p = [p[1], p[2], next];
+    }
+    result.push(&input[word_start - SENTINEL_LEN..]);
+    result
+}
 
-    let mut seg = vec!["B3", "B2", "B1"];
-    let mut ctype = vec![Ctype::O; 3];
-    let mut indices = vec![];
-
-    for (s, e) in input
-        .char_indices()
-        .map(|(i, _)| i)
-        .chain([input.len()])
-        .tuple_windows()
-    {
-        let ch = &input[s..e];
-        seg.push(ch);
-        ctype.push(ctype_(ch));
-        indices.push(s);
-    }
-
-    seg.push("E1");
-    seg.push("E2");
-    seg.push("E3");
-    ctype.push(Ctype::O);
-    ctype.push(Ctype::O);
-    ctype.push(Ctype::O);
-
-    let mut word = seg[3].to_string();
-    let mut p1 = Ctype::U;
-    let mut p2 = Ctype::U;
-    let mut p3 = Ctype::U;
-
-    let mut word_start = 0;
-    for i in 4..(seg.len() - 3) {
-        let mut score = BIAS;
-
-        let w1 = seg[i - 3];
-        let w2 = seg[i - 2];
-        let w3 = seg[i - 1];
-        let w4 = seg[i];
-        let w5 = seg[i + 1];
-        let w6 = seg[i + 2];
-
-        let c1 = ctype[i - 3];
-        let c2 = ctype[i - 2];
-        let c3 = ctype[i - 1];
-        let c4 = ctype[i];
-        let c5 = ctype[i + 1];
-        let c6 = ctype[i + 2];
-
-        score += up1(p1);
-        score += up2(p2);
-        score += up3(p3);
-
-        score += bp1(p1, p2);
-        score += bp2(p2, p3);
-
-        score += UW1.get(w1).unwrap_or(&0);
-        score += UW2.get(w2).unwrap_or(&0);
-        score += UW3.get(w3).unwrap_or(&0);
-        score += UW4.get(w4).unwrap_or(&0);
-        score += UW5.get(w5).unwrap_or(&0);
-        score += UW6.get(w6).unwrap_or(&0);
-
-        score += BW1.get(&format!("{w2}{w3}")).unwrap_or(&0);
-        score += BW2.get(&format!("{w3}{w4}")).unwrap_or(&0);
-        score += BW3.get(&format!("{w4}{w5}")).unwrap_or(&0);
-
-        score += TW1.get(&format!("{w1}{w2}{w3}")).unwrap_or(&0);
-        score += TW2.get(&format!("{w2}{w3}{w4}")).unwrap_or(&0);
-        score += TW3.get(&format!("{w3}{w4}{w5}")).unwrap_or(&0);
-        score += TW4.get(&format!("{w4}{w5}{w6}")).unwrap_or(&0);
-
-        score += uc1(c1);
-        score += uc2(c2);
-        score += uc3(c3);
-        score += uc4(c4);
-        score += uc5(c5);
-        score += uc6(c6);
-
-        score += bc1(c2, c3);
-        score += bc2(c3, c4);
-        score += bc3(c4, c5);
-
-        score += tc1(c1, c2, c3);
-        score += tc2(c2, c3, c4);
-        score += tc3(c3, c4, c5);
-        score += tc4(c4, c5, c6);
-
-        score += uq1(p1, c1);
-        score += uq2(p2, c2);
-        score += uq3(p3, c3);
-
-        score += bq1(p2, c2, c3);
-        score += bq2(p2, c3, c4);
-        score += bq3(p3, c2, c3);
-        score += bq4(p3, c3, c4);
-
-        score += tq1(p2, c1, c2, c3);
-        score += tq2(p2, c2, c3, c4);
-        score += tq3(p3, c1, c2, c3);
-        score += tq4(p3, c2, c3, c4);
-
-        let mut p = Ctype::O;
-        if score > 0 {
-            result.push(&input[indices[word_start]..indices[i - 3]]);
-            word_start = i - 3;
-            p = Ctype::B;
+/// 分割せずに 1 つの単語として扱う語の辞書 (ユーザー辞書)
+/// クライアント (segmenter.js) の辞書と同じ方法で照合する必要があります。
+#[derive(Default)]
+pub struct Dictionary {
+    /// 先頭の文字ごとの語 (長い順)
+    terms: HashMap<char, Vec<String>>,
+}
+
+impl Dictionary {
+    /// 語の列から辞書を作成します。空の語は無視します。
+    /// 検索用の単語は正規化した文字列から切り出すため、語も [`normalize`] で正規化します。
+    pub fn new<S: AsRef<str>>(terms: impl IntoIterator<Item = S>) -> Self {
+        let mut dict: HashMap<char, Vec<String>> = HashMap::new();
+        terms
+            .into_iter()
+            .map(|t| normalize(t.as_ref()).trim().to_owned())
+            .filter_map(|t| Some((t.chars().next()?, t)))
+            .for_each(|(c, t)| dict.entry(c).or_default().push(t));
+        dict.values_mut().for_each(|ts| {
+            ts.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+            ts.dedup();
+        });
+        Self { terms: dict }
+    }
+
+    /// `s` の先頭に一致する最も長い語のバイト数を返します。
+    fn longest_match(&self, s: &str) -> Option<usize> {
+        let first = s.chars().next()?;
+        self.terms
+            .get(&first)?
+            .iter()
+            .find(|t| s.starts_with(&t[..]))
+            .map(String::len)
+    }
+}
+
+/// 辞書を使って文字列を単語に分割します。
+/// 先頭から順に、辞書の語のうち最も長く一致するものを 1 つの単語として切り出し、
+/// 残りの部分をモデルで分割します。
+pub fn segment_with<'a>(input: &'a str, dict: &Dictionary) -> Vec<&'a str> {
+    let mut result = vec![];
+    let mut rest_start = 0;
+    let mut i = 0;
+    while let Some(c) = input[i..].chars().next() {
+        match dict.longest_match(&input[i..]) {
+            Some(len) => {
+                result.extend(segment(&input[rest_start..i]));
+                result.push(&input[i..i + len]);
+                i += len;
+                rest_start = i;
+            }
+            None => i += c.len_utf8(),
         }
-        (p1, p2, p3) = (p2, p3, p);
-        word.push_str(seg[i]);
     }
-    result.push(&input[indices[word_start]..]);
+    result.extend(segment(&input[rest_start..]));
     result
 }
 
 #[cfg(test)]
 mod test {
-    use crate::util::segmenter::segment;
+    use super::{Ctype, ctype_, score};
+    use crate::include_asset;
+    use crate::util::segmenter::{Dictionary, segment, segment_with};
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+    use regex::Regex;
+    use std::io::Write as _;
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    /// 書き換え前の文字の種類の判定 (正規表現による)
+    fn reference_ctype(str: &str) -> Ctype {
+        static PATTERNS: OnceLock<Vec<(Regex, Ctype)>> = OnceLock::new();
+        let patterns = PATTERNS.get_or_init(|| {
+            [
+                ("[一二三四五六七八九十百千万億兆]", Ctype::M),
+                ("[一-龠々〆ヵヶ]", Ctype::H),
+                ("[ぁ-ん]", Ctype::I),
+                ("[ァ-ヴーｱ-ﾝﾞｰ]", Ctype::K),
+                ("[a-zA-Zａ-ｚＡ-Ｚ]", Ctype::A),
+                ("[0-9０-９]", Ctype::N),
+            ]
+            .into_iter()
+            .map(|(p, c)| (Regex::new(p).unwrap(), c))
+            .collect()
+        });
+        patterns
+            .iter()
+            .find(|(p, _)| p.is_match(str))
+            .map_or(Ctype::O, |(_, c)| *c)
+    }
+
+    /// 書き換え前の分割の実装 (文字ごとに文字列を切り出し、照合のたびに連結する)
+    fn reference_segment(input: &str) -> Vec<&str> {
+        if input.is_empty() {
+            return vec![];
+        }
+
+        let mut seg = vec!["B3", "B2", "B1"];
+        let mut ctype = vec![Ctype::O; 3];
+        let mut indices = vec![];
+        for (i, c) in input.char_indices() {
+            let ch = &input[i..i + c.len_utf8()];
+            seg.push(ch);
+            ctype.push(reference_ctype(ch));
+            indices.push(i);
+        }
+        seg.extend(["E1", "E2", "E3"]);
+        ctype.extend([Ctype::O; 3]);
+
+        let mut result = vec![];
+        let mut p = [Ctype::U; 3];
+        let mut word_start = 0;
+        for i in 4..(seg.len() - 3) {
+            let c = std::array::from_fn(|k| ctype[i - 3 + k]);
+            let w = |a: usize, b: usize| seg[i - 3 + a..i - 3 + b].concat();
+            let mut next = Ctype::O;
+            if score(p, c, w) > 0 {
+                result.push(&input[indices[word_start]..indices[i - 3]]);
+                word_start = i - 3;
+                next = Ctype::B;
+            }
+            p = [p[1], p[2], next];
+        }
+        result.push(&input[indices[word_start]..]);
+        result
+    }
+
+    /// 文字の種類や番兵と紛らわしい文字を含む、ランダムな文字列を作ります。
+    fn random_text(rng: &mut StdRng, len: usize) -> String {
+        let pool: Vec<char> =
+            "一二万億兆日本語漢字々〆ヵヶあいうがぎんっをァアヴーｱﾝﾞｰヷabzAZａｚＡＺ09０９\
+                               ,.、。「」｢｣・ 　\n-_B1E2Ｂ１Ｅ２😀𠮷"
+                .chars()
+                .collect();
+        (0..len)
+            .map(|_| pool[rng.random_range(0..pool.len())])
+            .collect()
+    }
+
+    /// 差分テストに使う文章
+    fn corpus() -> Vec<String> {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut texts: Vec<_> = include_asset!("../README.md")
+            .lines()
+            .map(str::to_owned)
+            .collect();
+        texts.extend((0..2000).map(|i| random_text(&mut rng, i % 50 + 1)));
+        texts
+    }
 
     #[test]
     fn test() {
         println!("{:?}", segment("今日もいい天気"));
     }
+
+    #[test]
+    fn test_ctype() {
+        let chars = ('\0'..='\u{ffff}').chain(['😀', '𠮷']);
+        for c in chars {
+            let expected = reference_ctype(c.encode_utf8(&mut [0; 4]));
+            assert_eq!(ctype_(c), expected, "{c:?}");
+        }
+    }
+
+    /// 書き換え前の実装と同じ結果になることを確認します。
+    #[test]
+    fn test_same_as_reference() {
+        for text in corpus() {
+            assert_eq!(segment(&text), reference_segment(&text), "{text:?}");
+        }
+    }
+
+    /// クライアントの実装 (segmenter.js) と同じ結果になることを確認します。
+    /// node がない環境では何もしません。
+    #[test]
+    fn test_same_as_client() {
+        let texts = corpus();
+        let script = [
+            include_asset!("segmenter.js"),
+            "const texts = JSON.parse(require('fs').readFileSync(0, 'utf8'));",
+            "console.log(JSON.stringify(texts.map(segment)));",
+        ]
+        .join("\n");
+
+        let child = std::process::Command::new("node")
+            .args(["-e", &script])
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+            Err(e) => panic!("{e}"),
+        };
+        let input = serde_json::to_string(&texts).unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let actual: Vec<Vec<String>> = serde_json::from_slice(&output.stdout).unwrap();
+        for (text, actual) in texts.iter().zip(actual) {
+            assert_eq!(actual, segment(text), "{text:?}");
+        }
+    }
+
+    /// 大きな文書の分割にかかる時間を計測します。
+    /// `cargo test --release bench_segment -- --ignored --nocapture` で実行します。
+    #[test]
+    #[ignore]
+    fn bench_segment() {
+        let corpus = corpus().join("\n");
+        let text = corpus.repeat((1 << 20) / corpus.len() + 1);
+        let mb = text.len() as f64 / 1024.0 / 1024.0;
+
+        let measure = |name: &str, f: &dyn Fn(&str) -> usize| {
+            let start = Instant::now();
+            let words = f(&text);
+            let secs = start.elapsed().as_secs_f64();
+            println!(
+                "{name}: {mb:.1} MiB, {words} words in {secs:.3} s ({:.1} MiB/s)",
+                mb / secs
+            );
+        };
+        measure("segment", &|t| segment(t).len());
+        measure("reference", &|t| reference_segment(t).len());
+    }
+
+    #[test]
+    fn test_dictionary() {
+        let text = "静的サイトジェネレーターでブルームフィルタを使う";
+        let dict = Dictionary::new(["ブルームフィルタ", "静的サイト", "静的サイトジェネレーター"]);
+        let words = segment_with(text, &dict);
+        assert_eq!(words.concat(), text);
+        assert!(words.contains(&"静的サイトジェネレーター"));
+        assert!(words.contains(&"ブルームフィルタ"));
+
+        // 空の辞書ではモデルのみで分割する
+        assert_eq!(segment_with(text, &Dictionary::default()), segment(text));
+    }
+
+    /// クライアントの実装 (segmenter.js) も辞書を同じ方法で使うことを確認します。
+    /// node がない環境では何もしません。
+    #[test]
+    fn test_dictionary_client() {
+        let terms = [
+            "ブルームフィルタ",
+            "静的サイト",
+            "静的サイトジェネレーター",
+            "全文検索",
+        ];
+        let texts = [
+            "静的サイトジェネレーターでブルームフィルタを使う",
+            "全文検索と全文検索エンジン、静的サイトの静的サイトジェネレーター",
+            "辞書の語を含まない文章です。",
+            "",
+        ];
+
+        let dict = Dictionary::new(terms);
+        let expected: Vec<_> = texts.iter().map(|t| segment_with(t, &dict)).collect();
+
+        let script = [
+            include_asset!("segmenter.js"),
+            include_asset!("search_query.js"),
+            &format!("setDictionary({});", serde_json::to_string(&terms).unwrap()),
+            &format!(
+                "console.log(JSON.stringify({}.map(segment)));",
+                serde_json::to_string(&texts).unwrap()
+            ),
+        ]
+        .join("\n");
+
+        let output = match std::process::Command::new("node")
+            .args(["-e", &script])
+            .output()
+        {
+            Ok(output) => output,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+            Err(e) => panic!("{e}"),
+        };
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let actual: Vec<Vec<String>> = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(actual, expected);
+    }
 }
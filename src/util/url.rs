@@ -0,0 +1,200 @@
+use std::path::{Path, PathBuf};
+
+/// リンクに書く URL の形式
+/// 設定の `url_style` と `publish_url` から作ります。
+#[derive(Clone, Debug)]
+pub struct LinkStyle {
+    /// 記事を `foo/index.html` に出力し、 `foo/` でリンクするか
+    pretty: bool,
+    /// サイトを公開するパス (`/` で始まり `/` で終わる)
+    base_path: String,
+}
+
+impl Default for LinkStyle {
+    fn default() -> Self {
+        Self::new(false, "/".to_owned())
+    }
+}
+
+impl LinkStyle {
+    /// `base_path` はサイトを公開するパス (`/` で始まり `/` で終わる) です。
+    pub fn new(pretty: bool, base_path: String) -> Self {
+        Self { pretty, base_path }
+    }
+
+    /// 記事を `foo/index.html` に出力し、 `foo/` でリンクするか
+    pub fn is_pretty(&self) -> bool {
+        self.pretty
+    }
+
+    /// 出力先のパスを、リンクに書く URL に変換します。
+    /// pretty URL の場合は、末尾の `index.html` を省きます。
+    pub fn href_of(&self, path: impl AsRef<Path>) -> String {
+        let path = path.as_ref().to_str().unwrap();
+        if self.pretty {
+            pretty_href(path)
+        } else {
+            path.to_owned()
+        }
+    }
+
+    /// ルートからの絶対パス (`/foo.html`) を、ベースパスからの絶対パス (`/blog/foo.html`) に変換します。
+    pub fn with_base_path(&self, path: &str) -> String {
+        join_base_path(&self.base_path, path)
+    }
+}
+
+fn join_base_path(base_path: &str, path: &str) -> String {
+    format!("{base_path}{}", path.trim_start_matches('/'))
+}
+
+/// 公開先 URL を、オリジン (`https://example.com`) とベースパス (`/blog/`) に分けます。
+/// ベースパスは必ず `/` で始まり `/` で終わります。
+pub fn split_site_url(url: &str) -> (&str, String) {
+    let host_start = url.find("://").map_or(0, |i| i + 3);
+    let (origin, path) = match url[host_start..].find('/') {
+        Some(i) => url.split_at(host_start + i),
+        None => (url, ""),
+    };
+    // クエリやフラグメントはベースパスに含めない
+    let path = path.split(['?', '#']).next().unwrap().trim_matches('/');
+    if path.is_empty() {
+        (origin, "/".to_owned())
+    } else {
+        (origin, format!("/{path}/"))
+    }
+}
+
+/// URL のパスに使えない文字 (空白や非 ASCII 文字など) をパーセントエンコードします。
+pub fn encode_url_path(path: &str) -> String {
+    const ALLOWED: &[u8] = b"-._~/!$&'()*+,;=:@%";
+    path.bytes().fold(String::new(), |mut s, b| {
+        if b.is_ascii_alphanumeric() || ALLOWED.contains(&b) {
+            s.push(b as char);
+        } else {
+            s += &format!("%{b:02X}");
+        }
+        s
+    })
+}
+
+/// URL のパスのパーセントエンコードを元に戻します。
+/// `%` に続く 2 桁が 16 進数でない場合は、そのままにします。
+pub fn decode_url_path(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok());
+        match hex.map(|h| u8::from_str_radix(h, 16)) {
+            Some(Ok(b)) if bytes[i] == b'%' => {
+                decoded.push(b);
+                i += 3;
+            }
+            _ => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// 末尾の `index.html` を省いた URL を返します。
+pub fn pretty_href(path: &str) -> String {
+    match path.strip_suffix("index.html") {
+        Some("") => "./".to_owned(),
+        Some(dir) if dir.ends_with('/') => dir.to_owned(),
+        _ => path.to_owned(),
+    }
+}
+
+/// 記事の出力先を pretty URL 用に変換します (`foo.html` は `foo/index.html` になります)。
+pub fn pretty_dst(path: PathBuf) -> PathBuf {
+    if path.file_name().is_some_and(|f| f == "index.html") {
+        path
+    } else {
+        path.with_extension("").join("index.html")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        LinkStyle, decode_url_path, encode_url_path, join_base_path, pretty_dst, pretty_href,
+        split_site_url,
+    };
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_pretty_href() {
+        assert_eq!(pretty_href("../public/foo/index.html"), "../public/foo/");
+        assert_eq!(pretty_href("/public/foo/index.html"), "/public/foo/");
+        assert_eq!(pretty_href("index.html"), "./");
+        assert_eq!(pretty_href("./index.html"), "./");
+        assert_eq!(pretty_href("../public/foo.html"), "../public/foo.html");
+        assert_eq!(pretty_href("../myindex.html"), "../myindex.html");
+    }
+
+    #[test]
+    fn test_pretty_dst() {
+        let dst = pretty_dst(PathBuf::from("public/foo.html"));
+        assert_eq!(dst, PathBuf::from("public/foo/index.html"));
+        let dst = pretty_dst(PathBuf::from("public/trip/index.html"));
+        assert_eq!(dst, PathBuf::from("public/trip/index.html"));
+    }
+
+    #[test]
+    fn test_split_site_url() {
+        let (origin, base) = split_site_url("https://example.com/blog/");
+        assert_eq!((origin, base.as_str()), ("https://example.com", "/blog/"));
+        let (origin, base) = split_site_url("https://example.com/a/b");
+        assert_eq!((origin, base.as_str()), ("https://example.com", "/a/b/"));
+        let (origin, base) = split_site_url("https://example.com");
+        assert_eq!((origin, base.as_str()), ("https://example.com", "/"));
+        let (origin, base) = split_site_url("https://example.com/");
+        assert_eq!((origin, base.as_str()), ("https://example.com", "/"));
+    }
+
+    #[test]
+    fn test_join_base_path() {
+        assert_eq!(join_base_path("/blog/", "/style.css"), "/blog/style.css");
+        assert_eq!(join_base_path("/", "/public/foo.html"), "/public/foo.html");
+
+        let style = LinkStyle::new(true, "/blog/".to_owned());
+        assert_eq!(style.with_base_path("/img/a.png"), "/blog/img/a.png");
+        assert_eq!(style.href_of("public/foo/index.html"), "public/foo/");
+        let style = LinkStyle::default();
+        assert_eq!(style.with_base_path("/img/a.png"), "/img/a.png");
+        assert_eq!(
+            style.href_of("public/foo/index.html"),
+            "public/foo/index.html"
+        );
+    }
+
+    #[test]
+    fn test_encode_url_path() {
+        assert_eq!(encode_url_path("public/a b.html"), "public/a%20b.html");
+        assert_eq!(
+            encode_url_path("public/日記.html"),
+            "public/%E6%97%A5%E8%A8%98.html"
+        );
+        assert_eq!(
+            encode_url_path("public/foo-1_2.html"),
+            "public/foo-1_2.html"
+        );
+    }
+
+    #[test]
+    fn test_decode_url_path() {
+        assert_eq!(decode_url_path("my%20photo.png"), "my photo.png");
+        assert_eq!(decode_url_path("%E6%97%A5%E8%A8%98/a.png"), "日記/a.png");
+        assert_eq!(decode_url_path("100%.png"), "100%.png");
+        assert_eq!(decode_url_path("a%2"), "a%2");
+        assert_eq!(decode_url_path("a%zz%41"), "a%zzA");
+        let path = "public/日記 1.html";
+        assert_eq!(decode_url_path(&encode_url_path(path)), path);
+    }
+}